@@ -9,21 +9,32 @@ use std::{collections::HashSet, sync::Mutex};
 use scraper::Html;
 use url::Url;
 
-use crate::cli::Content;
+use crate::cli::{Cli, Content, Display};
 use crate::extract;
+use crate::forms;
+use crate::markdown;
+use crate::meta;
+use crate::network::{self, Capture, Exchanges, Response};
 use crate::node;
+use crate::patterns;
+use crate::rules;
+use crate::structured;
+use crate::tables;
+use crate::text;
 
 pub struct Browser {
     #[allow(dead_code)] // need to keep the browser alive
     browser: headless_chrome::Browser,
     pub tab: Arc<headless_chrome::Tab>,
+    exchanges: Exchanges,
+    page: Arc<Mutex<Option<Response>>>,
 }
 
 impl Browser {
     // These functions are used in async context
     // The separation of function is needed to send connection in async
     // task, but the Html can't be sent accros async task
-    pub fn new_navigate(url: &Url) -> Result<Self, BrowseError> {
+    pub fn new_navigate(url: &Url, capture: Option<Capture>) -> Result<Self, BrowseError> {
         let browser = headless_chrome::Browser::new(
             LaunchOptions::default_builder()
                 .devtools(false)
@@ -31,28 +42,57 @@ impl Browser {
                 .map_err(|e| BrowseError::Browser(e.to_string()))?,
         )?;
         let tab = browser.new_tab()?;
+        let exchanges = Exchanges::default();
+        let page = Arc::default();
+        // The response of the page is always kept to know its status
+        network::record_page(&tab, &page)?;
+        if let Some(capture) = capture {
+            network::record(&tab, capture, &exchanges)?;
+        }
         tab.navigate_to(url.as_str())?;
         tab.wait_until_navigated()?;
-        Ok(Self { browser, tab })
+        Ok(Self {
+            browser,
+            tab,
+            exchanges,
+            page,
+        })
     }
 
     // TODO : replace handle_... by the command and format
     // Add format
     // Extract useful information
-    pub async fn parse_document(
-        self,
-        contents: &Vec<Content>,
-        node: &Arc<Mutex<node::Node>>,
-    ) -> HashSet<Url> {
+    pub async fn parse_document(self, args: &Cli, node: &Arc<Mutex<node::Node>>) -> HashSet<Url> {
         let response = self.tab.get_content().unwrap();
         let document = Html::parse_document(&response);
         let links = extract::extract_links(&node.lock().unwrap().url, &document);
+        let anchors = extract::extract_anchors(&node.lock().unwrap().url, &document);
+        node.lock().unwrap().anchors = anchors;
+
+        if let Display::Mirror { .. } = args.cmd {
+            let mut node = node.lock().unwrap();
+            node.assets = Some(extract::extract_assets(&node.url, &document));
+            node.html = Some(response.clone());
+        }
+
+        if let Some(page) = self.page.lock().unwrap().take() {
+            let mut node = node.lock().unwrap();
+            node.status = Some(page.status);
+            node.mime = Some(page.mime);
+        }
+        if args.capture().is_some() {
+            node.lock().unwrap().exchanges =
+                Some(std::mem::take(&mut *self.exchanges.lock().unwrap()));
+        }
 
-        for content in contents {
+        for content in &args.content {
             match content {
                 Content::Texts => {
                     extract::extract_texts(node, &document);
                 }
+                Content::Visible => {
+                    text::extract_visible(node, &document, args.main_content);
+                }
                 Content::Comments => {
                     extract::extract_comments(node, &document);
                 }
@@ -67,15 +107,48 @@ impl Browser {
                 Content::Inputs => {
                     extract::extract_input(node, &document);
                 }
+                Content::Forms => {
+                    forms::extract_forms(node, &document);
+                }
+                Content::Meta => {
+                    meta::extract_meta(node, &document);
+                }
+                Content::Structured => {
+                    structured::extract_structured(node, &document);
+                }
+                Content::Tables => {
+                    tables::extract_tables(node, &document);
+                }
+                Content::Markdown => {
+                    markdown::extract_markdown(node, &document);
+                }
                 Content::All => {
                     extract::extract_texts(node, &document);
+                    text::extract_visible(node, &document, args.main_content);
                     extract::extract_comments(node, &document);
                     node.lock().unwrap().links = Some(links.clone().into_iter().collect());
                     extract::extract_images(node, &document);
                     extract::extract_input(node, &document);
+                    forms::extract_forms(node, &document);
+                    meta::extract_meta(node, &document);
+                    structured::extract_structured(node, &document);
+                    tables::extract_tables(node, &document);
+                    markdown::extract_markdown(node, &document);
                 }
             };
         }
+
+        extract::extract_selections(node, &document, &args.selections());
+        extract::extract_xpaths(node, &document, &args.xpath);
+        patterns::extract_matches(node, &document, &response, &args.regex, &args.regex_in);
+        if let Some(rules) = &args.rules {
+            rules::extract_records(node, &document, &response, rules);
+        }
+
+        // Images are needed to download them even if they are not part of the output
+        if args.download_images.is_some() && node.lock().unwrap().images.is_none() {
+            extract::extract_images(node, &document);
+        }
         links
     }
 }
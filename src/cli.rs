@@ -1,9 +1,16 @@
 use core::fmt;
+use std::{path::PathBuf, time::Duration};
 
 use clap::{Parser, Subcommand};
 use colored::Colorize;
+use scraper::Selector;
 use url::Url;
 
+use crate::extract::{Selection, XPathSelection};
+use crate::network::Capture;
+use crate::patterns::Pattern;
+use crate::rules::Rules;
+
 /// Website scraper
 #[derive(Parser, Debug)]
 #[command(name = "Coma")]
@@ -26,9 +33,9 @@ pub struct Cli {
     #[arg(short, long, value_delimiter = ',', default_value = "all")]
     pub content: Vec<Content>,
 
-    /// Url to start the search
+    /// Url to start the search, needed by every command crawling
     #[arg(short, long)]
-    pub url: String,
+    pub url: Option<String>,
 
     /// Depth to search from the given url, 0 for only the current url, < 0 for infinite depth
     #[arg(short, long, default_value_t = 0, allow_negative_numbers = true)]
@@ -46,6 +53,47 @@ pub struct Cli {
     // Depth to external website with different domain. Depth have priority to stop the search
     #[arg(short, long, default_value_t = 0)]
     pub external: i32,
+
+    /// Download every image found in this directory
+    #[arg(long, value_name = "DIR")]
+    pub download_images: Option<PathBuf>,
+
+    /// Template used by the template format, with a Jinja-like syntax
+    #[arg(long, value_name = "FILE")]
+    pub template: Option<PathBuf>,
+
+    /// Value to extract from every page, as name=selector. Add @attr to take an attribute
+    /// or @html to take the inner html instead of the text. Can be repeated
+    #[arg(long, value_name = "NAME=SELECTOR[@ATTR]")]
+    pub select: Vec<Selection>,
+
+    /// Value to extract from every page with an XPath expression, as name=expr. Nodes
+    /// give their text and attributes their value. Can be repeated
+    #[arg(long, value_name = "NAME=EXPR")]
+    pub xpath: Vec<XPathSelection>,
+
+    /// Regex searched in every page, as name=pattern or the name of a preset: emails,
+    /// phones, ipv4, uuids, aws-keys, jwt, urls-in-js. Can be repeated
+    #[arg(long, value_name = "NAME=PATTERN|PRESET")]
+    pub regex: Vec<Pattern>,
+
+    /// Parts of the page searched by the regexes
+    #[arg(long, value_delimiter = ',', default_value = "texts")]
+    pub regex_in: Vec<RegexSource>,
+
+    /// Toml file of extraction rules, giving structured records for the pages they apply to
+    #[arg(long, value_name = "FILE", value_parser = Rules::load)]
+    pub rules: Option<Rules>,
+
+    /// Keep only the main content of the pages in the visible text, without the
+    /// navigation, header, footer and sidebars
+    #[arg(long)]
+    pub main_content: bool,
+
+    /// Render the template for every page or once for the whole crawl. The renders of
+    /// the pages are joined by a new line
+    #[arg(long, value_enum, default_value = "page")]
+    pub template_scope: TemplateScope,
 }
 
 #[derive(Subcommand, Debug, Clone, PartialEq, Eq, Hash)]
@@ -68,8 +116,105 @@ pub enum Display {
         name: String,
     },
 
-    /// Create a html topolgy
-    Graph,
+    /// Create a html topolgy, or export it for other tools
+    Graph {
+        /// Format of the graph
+        #[arg(short, long, value_enum, default_value = "html")]
+        format: GraphFormat,
+
+        /// File where the graph is written. By default the html is written in a
+        /// temporary file and the other formats are printed
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Don't open the html graph in the web browser
+        #[arg(long)]
+        no_open: bool,
+
+        /// Size and color the nodes of the html graph by this metric
+        #[arg(short, long, value_enum)]
+        metric: Option<GraphMetric>,
+    },
+
+    /// Serve the results in a local web interface, updated during the crawl
+    Serve {
+        /// Port of the server
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+
+        /// Address of the server, 0.0.0.0 to reach it from other machines
+        #[arg(short, long, default_value = "127.0.0.1")]
+        address: String,
+    },
+
+    /// Compare two crawls saved with the snapshot format
+    Diff {
+        /// Snapshot of the previous crawl
+        old: PathBuf,
+
+        /// Snapshot of the new crawl
+        new: PathBuf,
+
+        /// Print the differences as json
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Crawl again on a schedule and report the changes since the previous crawl
+    Watch {
+        /// Time between two crawls, like 30m, 1h or 1d
+        #[arg(short, long, value_parser = humantime::parse_duration, default_value = "1h")]
+        interval: Duration,
+
+        /// Css selector whose content is compared between the crawls, can be repeated
+        #[arg(short, long)]
+        selector: Vec<String>,
+
+        /// Append the changes to this file, one json event per line
+        #[arg(short, long, value_name = "FILE")]
+        log: Option<PathBuf>,
+
+        /// Send every change as json to this url with a POST request
+        #[arg(short, long, value_name = "URL")]
+        webhook: Option<String>,
+    },
+
+    /// Save the pages and their assets in a directory tree browsable offline
+    Mirror {
+        /// Directory where the site is mirrored
+        #[arg(short, long, default_value = "mirror")]
+        directory: PathBuf,
+
+        /// Rewrite the links to point at the local copies
+        #[arg(short, long)]
+        rewrite: bool,
+    },
+
+    /// Archive the pages in a WARC file
+    Warc {
+        /// Name of the output file, without the .warc.gz extension
+        #[arg(short, long, default_value = "output")]
+        name: String,
+
+        /// Also archive the assets loaded by the pages
+        #[arg(short, long)]
+        assets: bool,
+    },
+
+    /// Export the network traffic of the pages in HAR files
+    Har {
+        /// Name of the output file, without the .har extension
+        #[arg(short, long, default_value = "output")]
+        name: String,
+
+        /// Write one file per page instead of a single file
+        #[arg(short, long)]
+        split: bool,
+
+        /// Include the body of the responses
+        #[arg(short, long)]
+        bodies: bool,
+    },
 }
 
 #[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, serde::Serialize)]
@@ -77,6 +222,10 @@ pub enum Content {
     /// Extract the text in the html
     Texts,
 
+    /// Extract the visible text as paragraphs, headings and list items, without the
+    /// scripts, styles and hidden elements
+    Visible,
+
     /// Extract the comments in the html
     Comments,
 
@@ -86,13 +235,47 @@ pub enum Content {
     /// Extract the images of the page
     Images,
 
-    /// Extract informations about any form
+    /// Extract the inputs of the page
     Inputs,
 
+    /// Extract the forms with their action, method and fields
+    Forms,
+
+    /// Extract the title, meta tags, OpenGraph and Twitter properties, canonical link,
+    /// alternates, favicon and language
+    Meta,
+
+    /// Extract the JSON-LD, Microdata and RDFa data
+    Structured,
+
+    /// Extract the tables as headers and rows, also saved in csv files
+    Tables,
+
+    /// Convert the main content of the pages to markdown, also saved in one file per page
+    Markdown,
+
     /// Extract all information and generate a topology
     All,
 }
 
+#[derive(
+    clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum RegexSource {
+    /// Text of the page, without the scripts and styles
+    Texts,
+
+    /// Rendered html of the page
+    Html,
+
+    /// Comments in the html
+    Comments,
+
+    /// Inline scripts
+    Scripts,
+}
+
 #[derive(clap::ValueEnum, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Format {
     /// Create a json file with the data
@@ -100,16 +283,145 @@ pub enum Format {
 
     /// Raw data. Link are not raw href but joined with domain
     Raw,
+
+    /// Markdown report of the whole crawl
+    Report,
+
+    /// Render the file given with --template
+    Template,
+
+    /// Tree of the url paths of the crawl
+    Tree,
+
+    /// Whole crawl in a single json document, to compare with the diff command
+    Snapshot,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GraphFormat {
+    /// Interactive page opened in the web browser
+    Html,
+
+    /// Graphviz
+    Dot,
+
+    /// GraphML, read by most graph tools
+    Graphml,
+
+    /// Gephi
+    Gexf,
+
+    /// Mermaid flowchart, to embed in documentation
+    Mermaid,
+
+    /// Nodes and edges as json
+    Json,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphMetric {
+    /// Importance of the page given by the links pointing to it
+    Pagerank,
+
+    /// Number of pages linking to the page
+    InDegree,
+
+    /// Number of pages linked by the page
+    OutDegree,
+
+    /// Minimum number of clicks from the seed
+    Depth,
+
+    /// Group of pages that can all reach each other
+    Component,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateScope {
+    /// Render the template for every page
+    Page,
+
+    /// Render the template once with every page
+    Crawl,
+}
+
+impl Display {
+    // Format of the output for the commands that have one
+    pub fn format(&self) -> Option<&Format> {
+        match self {
+            Display::Print { format } | Display::Save { format, .. } => Some(format),
+            _ => None,
+        }
+    }
+
+    // The command needs a crawl, with an url to start
+    pub fn crawl(&self) -> bool {
+        !matches!(self, Display::Diff { .. })
+    }
+}
+
+impl Cli {
+    // Url to start the search, checked by `args` for the commands crawling
+    pub fn url(&self) -> &str {
+        self.url.as_deref().unwrap_or_default()
+    }
+
+    // Selections given with --select and the selectors compared by watch
+    pub fn selections(&self) -> Vec<Selection> {
+        let mut selections = self.select.clone();
+        if let Display::Watch { selector, .. } = &self.cmd {
+            selections.extend(selector.iter().map(|selector| Selection::text(selector)));
+        }
+        selections
+    }
+
+    // Network traffic to record while browsing
+    pub fn capture(&self) -> Option<Capture> {
+        match self.cmd {
+            Display::Warc { assets, .. } => Some(Capture {
+                assets,
+                bodies: true,
+            }),
+            Display::Har { bodies, .. } => Some(Capture {
+                assets: true,
+                bodies,
+            }),
+            _ => None,
+        }
+    }
 }
 
 pub enum ArgsError {
     InvalidUrl(String),
+    InvalidSelector(String),
+    MissingUrl,
+    MissingTemplate,
+    UnusedTemplate,
 }
 
 impl ArgsError {
     fn print(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ArgsError::InvalidUrl(url) => write!(f, "{}: {}", "Invalid URL".red(), url),
+            ArgsError::InvalidSelector(selector) => {
+                write!(f, "{}: {}", "Invalid selector".red(), selector)
+            }
+            ArgsError::MissingUrl => write!(
+                f,
+                "{}: the command needs --url <URL> to start the crawl",
+                "Missing URL".red()
+            ),
+            ArgsError::MissingTemplate => write!(
+                f,
+                "{}: the template format needs --template <FILE>",
+                "Missing template".red()
+            ),
+            ArgsError::UnusedTemplate => write!(
+                f,
+                "{}: --template is only used by the template format",
+                "Unused template".red()
+            ),
         }
     }
 }
@@ -131,7 +443,32 @@ impl std::error::Error for ArgsError {}
 pub fn args() -> Result<Cli, ArgsError> {
     let args = Cli::parse();
 
-    match Url::parse(&args.url) {
+    if args.cmd.format() == Some(&Format::Template) && args.template.is_none() {
+        return Err(ArgsError::MissingTemplate);
+    }
+    if args.cmd.format() != Some(&Format::Template) && args.template.is_some() {
+        return Err(ArgsError::UnusedTemplate);
+    }
+
+    if let Display::Watch {
+        selector, webhook, ..
+    } = &args.cmd
+    {
+        if let Some(selector) = selector.iter().find(|s| Selector::parse(s).is_err()) {
+            return Err(ArgsError::InvalidSelector(selector.clone()));
+        }
+        if let Some(webhook) = webhook {
+            Url::parse(webhook).map_err(|e| ArgsError::InvalidUrl(format!("{webhook}: {e}")))?;
+        }
+    }
+
+    if !args.cmd.crawl() {
+        return Ok(args);
+    }
+    let Some(url) = &args.url else {
+        return Err(ArgsError::MissingUrl);
+    };
+    match Url::parse(url) {
         Ok(v) => {
             v.domain().ok_or(ArgsError::InvalidUrl(v.to_string()))?;
             Ok(args)
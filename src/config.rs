@@ -13,11 +13,9 @@ pub struct Config {
 }
 
 impl Config {
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let args = cli::args()?;
-
+    pub fn new(args: cli::Cli) -> Result<Self, Box<dyn std::error::Error>> {
         // NOTE: browser must still exist or the connection is closed. Pretty weird to not have
-        let origin_url = Url::parse(&args.url).map_err(|e| ConfigError::Message(e.to_string()))?;
+        let origin_url = Url::parse(args.url()).map_err(|e| ConfigError::Message(e.to_string()))?;
         origin_url
             .domain()
             .ok_or("Url doesn't have a domain")
@@ -32,6 +30,15 @@ impl Config {
         })
     }
 
+    // Start again from an empty tree, to crawl the site once more
+    pub fn reset(&mut self) {
+        let (url, id) = {
+            let root = self.root.lock().unwrap();
+            (root.url.clone(), root.id.clone())
+        };
+        self.root = Node::new_arc(None, url, id);
+    }
+
     pub fn same_domain(&self, url: &Url) -> bool {
         url.domain().unwrap_or("") == self.domain
     }
@@ -0,0 +1,249 @@
+use colored::Colorize;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    error, fmt,
+    path::Path,
+};
+
+use crate::extract::collapse;
+use crate::snapshot::{PageSnapshot, Snapshot};
+
+// Differences between two crawls of the same site
+#[derive(Serialize, Debug)]
+pub struct CrawlDiff {
+    // Dates of the two crawls
+    pub old: String,
+    pub new: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<PageDiff>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Change<T> {
+    pub old: T,
+    pub new: T,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct Changes {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+// Page present in both crawls, only the changed fields are kept
+#[derive(Serialize, Debug)]
+pub struct PageDiff {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<Change<Option<u32>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Change<Option<String>>>,
+    #[serde(skip_serializing_if = "Changes::is_empty")]
+    pub links: Changes,
+    #[serde(skip_serializing_if = "Changes::is_empty")]
+    pub images: Changes,
+    #[serde(skip_serializing_if = "Changes::is_empty")]
+    pub comments: Changes,
+    #[serde(skip_serializing_if = "Changes::is_empty")]
+    pub inputs: Changes,
+    #[serde(skip_serializing_if = "Changes::is_empty")]
+    pub texts: Changes,
+    // Content of the selectors, by name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub extracted: BTreeMap<String, Changes>,
+}
+
+impl Changes {
+    // Values only in one of the lists, in their order of appearance
+    fn between(old: &[String], new: &[String]) -> Self {
+        let only = |values: &[String], other: &[String]| {
+            let other: HashSet<&String> = other.iter().collect();
+            let mut seen = HashSet::new();
+            values
+                .iter()
+                .filter(|value| !other.contains(value) && seen.insert(*value))
+                .cloned()
+                .collect()
+        };
+        Changes {
+            added: only(new, old),
+            removed: only(old, new),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+fn change<T: PartialEq + Clone>(old: &T, new: &T) -> Option<Change<T>> {
+    (old != new).then(|| Change {
+        old: old.clone(),
+        new: new.clone(),
+    })
+}
+
+impl PageDiff {
+    fn new(old: &PageSnapshot, new: &PageSnapshot) -> Self {
+        let mut extracted = BTreeMap::new();
+        for name in old.extracted.keys().chain(new.extracted.keys()) {
+            let values =
+                |page: &PageSnapshot| page.extracted.get(name).cloned().unwrap_or_default();
+            let changes = Changes::between(&values(old), &values(new));
+            if !changes.is_empty() {
+                extracted.insert(name.clone(), changes);
+            }
+        }
+        PageDiff {
+            url: new.url.clone(),
+            status: change(&old.status, &new.status),
+            error: change(&old.error, &new.error),
+            links: Changes::between(&old.links, &new.links),
+            images: Changes::between(&old.images, &new.images),
+            comments: Changes::between(&old.comments, &new.comments),
+            inputs: Changes::between(&old.inputs, &new.inputs),
+            texts: Changes::between(&old.texts, &new.texts),
+            extracted,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.status.is_none()
+            && self.error.is_none()
+            && self.links.is_empty()
+            && self.images.is_empty()
+            && self.comments.is_empty()
+            && self.inputs.is_empty()
+            && self.texts.is_empty()
+            && self.extracted.is_empty()
+    }
+}
+
+// Pages are matched by their url
+pub fn compare(old: &Snapshot, new: &Snapshot) -> CrawlDiff {
+    let mut old_pages: HashMap<&str, &PageSnapshot> = HashMap::new();
+    for page in &old.pages {
+        old_pages.entry(&page.url).or_insert(page);
+    }
+    let mut new_urls: HashSet<&str> = HashSet::new();
+
+    let mut diff = CrawlDiff {
+        old: old.date.clone(),
+        new: new.date.clone(),
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+    };
+    for page in &new.pages {
+        if !new_urls.insert(&page.url) {
+            continue;
+        }
+        match old_pages.get(page.url.as_str()) {
+            Some(old_page) => {
+                let page_diff = PageDiff::new(old_page, page);
+                if !page_diff.is_empty() {
+                    diff.changed.push(page_diff);
+                }
+            }
+            None => diff.added.push(page.url.clone()),
+        }
+    }
+    let mut removed: HashSet<&str> = HashSet::new();
+    diff.removed = old
+        .pages
+        .iter()
+        .filter(|page| !new_urls.contains(page.url.as_str()) && removed.insert(&page.url))
+        .map(|page| page.url.clone())
+        .collect();
+    diff
+}
+
+pub fn run(old: &Path, new: &Path, json: bool) -> Result<(), Box<dyn error::Error>> {
+    let diff = compare(&Snapshot::read(old)?, &Snapshot::read(new)?);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else {
+        print!("{diff}");
+    }
+    Ok(())
+}
+
+fn status(status: &Option<u32>) -> String {
+    status.map_or("none".to_owned(), |status| status.to_string())
+}
+
+impl CrawlDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for CrawlDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Crawl of {} compared to {}", self.new, self.old)?;
+        if self.is_empty() {
+            return writeln!(f, "No changes");
+        }
+        if !self.added.is_empty() {
+            writeln!(f, "\n{} ({})", "Pages added".green(), self.added.len())?;
+            for url in &self.added {
+                writeln!(f, "  {} {}", "+".green(), url)?;
+            }
+        }
+        if !self.removed.is_empty() {
+            writeln!(f, "\n{} ({})", "Pages removed".red(), self.removed.len())?;
+            for url in &self.removed {
+                writeln!(f, "  {} {}", "-".red(), url)?;
+            }
+        }
+        if !self.changed.is_empty() {
+            writeln!(f, "\n{} ({})", "Pages changed".yellow(), self.changed.len())?;
+        }
+        for page in &self.changed {
+            writeln!(f, "\n  {}", page.url.bold())?;
+            if let Some(change) = &page.status {
+                writeln!(
+                    f,
+                    "    status: {} -> {}",
+                    status(&change.old),
+                    status(&change.new)
+                )?;
+            }
+            if let Some(change) = &page.error {
+                writeln!(
+                    f,
+                    "    error: {} -> {}",
+                    change.old.as_deref().unwrap_or("none"),
+                    change.new.as_deref().unwrap_or("none")
+                )?;
+            }
+            let mut lists = vec![
+                ("links", &page.links),
+                ("images", &page.images),
+                ("comments", &page.comments),
+                ("inputs", &page.inputs),
+                ("texts", &page.texts),
+            ];
+            lists.extend(
+                page.extracted
+                    .iter()
+                    .map(|(name, changes)| (name.as_str(), changes)),
+            );
+            for (name, changes) in lists {
+                if changes.is_empty() {
+                    continue;
+                }
+                writeln!(f, "    {name}:")?;
+                for value in &changes.added {
+                    writeln!(f, "      {} {}", "+".green(), collapse(value))?;
+                }
+                for value in &changes.removed {
+                    writeln!(f, "      {} {}", "-".red(), collapse(value))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
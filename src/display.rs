@@ -1,18 +1,29 @@
-use crate::cli::{Display, Format};
+use crate::cli::{Cli, Display, Format};
 use crate::graph;
 use crate::node::Node;
+use crate::template;
 
 impl Node {
-    pub fn display(node: &mut Node, cmd: &Display) -> std::result::Result<(), CommandError> {
-        match cmd {
+    pub fn display(node: &mut Node, args: &Cli) -> std::result::Result<(), CommandError> {
+        match &args.cmd {
             Display::Print { format: _ } => {
-                println!("{}", node.output.as_ref().unwrap())
+                if let Some(output) = &node.output {
+                    println!("{}", output)
+                }
             }
             Display::Save { format, name } => {
-                let output = node.output.as_ref().unwrap();
+                // Only the root has an output with the formats of the whole tree
+                let Some(output) = &node.output else {
+                    return Ok(());
+                };
                 let extension = match format {
-                    Format::Json => "json",
-                    Format::Raw => "txt",
+                    Format::Json | Format::Snapshot => "json".to_owned(),
+                    Format::Raw | Format::Tree => "txt".to_owned(),
+                    Format::Report => "md".to_owned(),
+                    Format::Template => args
+                        .template
+                        .as_deref()
+                        .map_or("txt".to_owned(), template::extension),
                 };
 
                 let file_name = format!("{name}.{extension}");
@@ -21,7 +32,14 @@ impl Node {
                 let mut file = File::create(path)?;
                 file.write_all(output.as_bytes())?;
             }
-            Display::Graph => graph::render(node)?,
+            // Written once for the whole tree, or served
+            Display::Graph { .. }
+            | Display::Serve { .. }
+            | Display::Diff { .. }
+            | Display::Watch { .. }
+            | Display::Mirror { .. }
+            | Display::Warc { .. }
+            | Display::Har { .. } => {}
         }
         Ok(())
     }
@@ -41,7 +59,10 @@ pub enum CommandError {
 
 impl fmt::Display for CommandError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "error in command data")
+        match self {
+            CommandError::IO(e) => write!(f, "error in command data: {e}"),
+            _ => write!(f, "error in command data"),
+        }
     }
 }
 
@@ -0,0 +1,212 @@
+use colored::Colorize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tokio::{sync::Semaphore, task::JoinSet};
+use url::Url;
+
+use crate::node::Node;
+
+// Image saved on the disk
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ImageFile {
+    pub url: String,
+    pub path: PathBuf,
+    pub size: usize,
+    pub mime: String,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+}
+
+impl fmt::Display for ImageFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} ({} bytes, {}",
+            self.url,
+            self.path.display(),
+            self.size,
+            self.mime
+        )?;
+        if let (Some(width), Some(height)) = (self.width, self.height) {
+            write!(f, ", {width}x{height}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+// Download every image found in the tree in `directory`. An url is fetched only once
+// and the file is named after the hash of its content, so the same image served at
+// different urls is saved only once.
+// The permits are the one of the crawl to keep the same concurrency.
+pub async fn download_images(
+    root: &Arc<Mutex<Node>>,
+    directory: &Path,
+    permits: &'static Semaphore,
+) -> Result<(), DownloadError> {
+    let mut urls: HashSet<Url> = HashSet::new();
+    Node::explore(root, &mut |node: &mut Node| {
+        urls.extend(node.images.iter().flatten().cloned());
+        Ok(())
+    })
+    .map_err(|e| DownloadError::Message(e.to_string()))?;
+    fs::create_dir_all(directory)?;
+
+    let client = reqwest::Client::new();
+    let mut handles = JoinSet::new();
+    for url in urls {
+        let permit = permits
+            .acquire()
+            .await
+            .map_err(|e| DownloadError::Message(e.to_string()))?;
+        let client = client.clone();
+        handles.spawn(async move {
+            let _permit = permit;
+            let response = fetch(&client, &url).await;
+            (url, response)
+        });
+    }
+
+    let mut files: HashMap<Url, ImageFile> = HashMap::new();
+    // Path of the file written for every content
+    let mut hashes: HashMap<String, PathBuf> = HashMap::new();
+    while let Some(handle) = handles.join_next().await {
+        let (url, response) = handle.map_err(|e| DownloadError::Message(e.to_string()))?;
+        let (bytes, mime) = match response {
+            Ok(response) => response,
+            Err(e) => {
+                eprintln!("{} {}: {}", "Failed to download".red(), url, e);
+                continue;
+            }
+        };
+
+        let hash: String = Sha256::digest(&bytes)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        let mime = mime.unwrap_or_else(|| sniff_mime(&bytes).to_owned());
+        // The same content served with another type or extension keeps its first file
+        let path = match hashes.get(&hash) {
+            Some(path) => path.clone(),
+            None => {
+                let path = directory.join(format!("{}.{}", &hash[..16], extension(&mime, &url)));
+                fs::write(&path, &bytes)?;
+                hashes.insert(hash, path.clone());
+                path
+            }
+        };
+
+        let dimensions = imagesize::blob_size(&bytes).ok();
+        files.insert(
+            url.clone(),
+            ImageFile {
+                url: url.to_string(),
+                path,
+                size: bytes.len(),
+                mime,
+                width: dimensions.as_ref().map(|d| d.width),
+                height: dimensions.as_ref().map(|d| d.height),
+            },
+        );
+    }
+
+    Node::explore(root, &mut |node: &mut Node| {
+        if let Some(images) = &node.images {
+            node.image_files = Some(
+                images
+                    .iter()
+                    .filter_map(|url| files.get(url).cloned())
+                    .collect(),
+            );
+        }
+        Ok(())
+    })
+    .map_err(|e| DownloadError::Message(e.to_string()))?;
+
+    println!(
+        "Downloaded {} images in {} files",
+        files.len().to_string().green(),
+        hashes.len().to_string().green()
+    );
+    Ok(())
+}
+
+// Body and mime type given by the server
+async fn fetch(client: &reqwest::Client, url: &Url) -> reqwest::Result<(Vec<u8>, Option<String>)> {
+    let response = client.get(url.as_str()).send().await?.error_for_status()?;
+    let mime = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(';').next().unwrap_or(value).trim().to_owned());
+    Ok((response.bytes().await?.to_vec(), mime))
+}
+
+fn sniff_mime(bytes: &[u8]) -> &'static str {
+    match imagesize::image_type(bytes) {
+        Ok(imagesize::ImageType::Png) => "image/png",
+        Ok(imagesize::ImageType::Jpeg) => "image/jpeg",
+        Ok(imagesize::ImageType::Gif) => "image/gif",
+        Ok(imagesize::ImageType::Webp) => "image/webp",
+        Ok(imagesize::ImageType::Bmp) => "image/bmp",
+        Ok(imagesize::ImageType::Ico) => "image/x-icon",
+        _ => "application/octet-stream",
+    }
+}
+
+fn extension(mime: &str, url: &Url) -> String {
+    let extension = match mime {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/avif" => "avif",
+        "image/bmp" => "bmp",
+        "image/svg+xml" => "svg",
+        "image/x-icon" | "image/vnd.microsoft.icon" => "ico",
+        // Trust the url when the server doesn't know
+        _ => Path::new(url.path())
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("bin"),
+    };
+    extension.to_owned()
+}
+
+pub enum DownloadError {
+    IO(std::io::Error),
+    Message(String),
+}
+
+impl DownloadError {
+    fn print(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloadError::IO(e) => write!(f, "{}: {}", "Download error".red(), e),
+            DownloadError::Message(s) => write!(f, "{}: {}", "Download error".red(), s),
+        }
+    }
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl fmt::Debug for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl std::error::Error for DownloadError {}
+
+impl From<std::io::Error> for DownloadError {
+    fn from(value: std::io::Error) -> Self {
+        DownloadError::IO(value)
+    }
+}
@@ -1,10 +1,19 @@
+use colored::Colorize;
+use regex::Regex;
 use std::{
     collections::HashSet,
-    sync::{Arc, Mutex},
+    str::FromStr,
+    sync::{Arc, LazyLock, Mutex},
 };
 
 use markup5ever::local_name;
-use scraper::{node::Element, Html};
+use scraper::{node::Element, ElementRef, Html, Selector};
+use serde::Serialize;
+use sxd_document::{
+    dom::{Document, Element as XmlElement},
+    Package, QName,
+};
+use sxd_xpath::{self as xpath, Context, Factory, Value};
 use url::Url;
 
 use crate::node;
@@ -32,6 +41,64 @@ pub fn extract_links(url: &Url, page: &Html) -> HashSet<Url> {
     }))
 }
 
+// Part of the page where a link is
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Position {
+    Nav,
+    Body,
+    Footer,
+}
+
+// Link of the page with the information given by its anchor
+#[derive(Debug, Clone)]
+pub struct Link {
+    pub url: Url,
+    pub text: String,
+    pub rel: Vec<String>,
+    pub position: Position,
+}
+
+pub fn extract_anchors(url: &Url, page: &Html) -> Vec<Link> {
+    let selector = Selector::parse("a[href]").unwrap();
+    page.select(&selector)
+        .filter_map(|anchor| {
+            let link = Url::join(url, anchor.value().attr("href")?).ok()?;
+            // The header of the page is part of the navigation
+            let position = anchor
+                .ancestors()
+                .filter_map(|node| node.value().as_element())
+                .find_map(|element| match element.name.local {
+                    local_name!("nav") | local_name!("header") => Some(Position::Nav),
+                    local_name!("footer") => Some(Position::Footer),
+                    _ => match element.attr("role") {
+                        Some("navigation" | "banner") => Some(Position::Nav),
+                        Some("contentinfo") => Some(Position::Footer),
+                        _ => None,
+                    },
+                })
+                .unwrap_or(Position::Body);
+            Some(Link {
+                url: link,
+                text: anchor
+                    .text()
+                    .collect::<String>()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                rel: anchor
+                    .value()
+                    .attr("rel")
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .map(str::to_lowercase)
+                    .collect(),
+                position,
+            })
+        })
+        .collect()
+}
+
 pub fn extract_comments(node: &Arc<Mutex<node::Node>>, page: &Html) {
     node.lock().unwrap().comments = Some(
         page.tree
@@ -74,6 +141,16 @@ where
         .collect()
 }
 
+// Value on a single line, with its whitespaces collapsed
+pub fn collapse(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Text of the element and its children on a single line
+pub fn element_text(element: ElementRef) -> String {
+    collapse(&element.text().collect::<Vec<_>>().join(" "))
+}
+
 pub fn extract_images(node: &Arc<Mutex<node::Node>>, page: &Html) {
     node.lock().unwrap().images = Some(extract_element(page, |element: Element| {
         if matches!(element.name.local, local_name!("img")) {
@@ -88,13 +165,341 @@ pub fn extract_images(node: &Arc<Mutex<node::Node>>, page: &Html) {
     }));
 }
 
-// TODO : better rendering
+// Inputs as they are written in the html, the forms content gives their details
 pub fn extract_input(node: &Arc<Mutex<node::Node>>, page: &Html) {
-    node.lock().unwrap().inputs = Some(extract_element(page, |element: Element| {
-        if matches!(element.name.local, local_name!("input")) {
-            Some(format!("{:?}", element))
-        } else {
-            None
+    let inputs = Selector::parse("input").unwrap();
+    node.lock().unwrap().inputs = Some(page.select(&inputs).map(|input| input.html()).collect());
+}
+
+// Files needed to render the page offline
+pub fn extract_assets(url: &Url, page: &Html) -> Vec<Url> {
+    let mut assets = extract_element(page, |element: Element| {
+        let attr = match element.name.local {
+            local_name!("img")
+            | local_name!("script")
+            | local_name!("source")
+            | local_name!("video")
+            | local_name!("audio") => "src",
+            local_name!("link") => {
+                let rel = element.attr("rel").unwrap_or_default();
+                if !rel.contains("stylesheet") && !rel.contains("icon") {
+                    return None;
+                }
+                "href"
+            }
+            _ => return None,
+        };
+        Url::join(url, element.attr(attr)?).ok()
+    });
+    assets.sort();
+    assets.dedup();
+    assets
+}
+
+// Value taken from the elements matched by a selection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    Text,
+    Html,
+    Attr(String),
+}
+
+impl Target {
+    // Name given after the selector, text and html aren't attributes
+    pub fn from_attr(attr: &str) -> Self {
+        match attr {
+            "text" => Target::Text,
+            "html" => Target::Html,
+            attr => Target::Attr(attr.to_owned()),
         }
-    }));
+    }
+}
+
+// Named css selector, written `name=selector` and followed by `@attr` to take an
+// attribute or `@html` to take the inner html instead of the text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    pub name: String,
+    pub selector: String,
+    pub target: Target,
+}
+
+impl Selection {
+    // The selector is used as the name
+    pub fn text(selector: &str) -> Self {
+        Selection {
+            name: selector.to_owned(),
+            selector: selector.to_owned(),
+            target: Target::Text,
+        }
+    }
+}
+
+// Split the name of an extraction from its expression
+fn named(s: &str) -> Option<(&str, &str)> {
+    s.split_once('=')
+        .map(|(name, rest)| (name.trim(), rest))
+        .filter(|(name, _)| !name.is_empty())
+}
+
+impl FromStr for Selection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, rest) = named(s).ok_or(format!("{s}: expected name=selector[@attr]"))?;
+        // An @ followed by anything else than a name is part of the selector,
+        // like in a[href$="@example.com"]
+        let (selector, target) = match rest.rsplit_once('@') {
+            Some((selector, attr))
+                if !attr.is_empty()
+                    && attr
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':')) =>
+            {
+                (selector, Target::from_attr(attr))
+            }
+            _ => (rest, Target::Text),
+        };
+        let selector = selector.trim();
+        Selector::parse(selector).map_err(|e| format!("{selector}: {e}"))?;
+        Ok(Selection {
+            name: name.to_owned(),
+            selector: selector.to_owned(),
+            target,
+        })
+    }
+}
+
+// Values of the elements matched by the selector, elements without the attribute
+// are skipped
+pub fn select(page: &Html, selector: &Selector, target: &Target) -> Vec<String> {
+    page.select(selector)
+        .filter_map(|element| match target {
+            Target::Text => Some(
+                element
+                    .text()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            Target::Html => Some(element.inner_html()),
+            Target::Attr(attr) => element.value().attr(attr).map(str::to_owned),
+        })
+        .collect()
+}
+
+// Values of the elements matched by the selections, by name. Selections sharing a
+// name are merged
+pub fn extract_selections(node: &Arc<Mutex<node::Node>>, page: &Html, selections: &[Selection]) {
+    let mut node = node.lock().unwrap();
+    for selection in selections {
+        // Already checked with the arguments
+        let Ok(selector) = Selector::parse(&selection.selector) else {
+            continue;
+        };
+        let values = select(page, &selector, &selection.target);
+        node.extracted
+            .entry(selection.name.clone())
+            .or_default()
+            .extend(values);
+    }
+}
+
+// Named XPath expression, written `name=expr`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XPathSelection {
+    pub name: String,
+    pub expr: String,
+}
+
+impl FromStr for XPathSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, expr) = named(s).ok_or(format!("{s}: expected name=expr"))?;
+        let expr = expr.trim();
+        check_xpath(expr)?;
+        Ok(XPathSelection {
+            name: name.to_owned(),
+            expr: expr.to_owned(),
+        })
+    }
+}
+
+pub fn check_xpath(expr: &str) -> Result<(), String> {
+    if let Some(prefix) = prefixes(expr)
+        .into_iter()
+        .find(|prefix| !NAMESPACES.iter().any(|(known, _)| known == prefix))
+    {
+        return Err(format!("{expr}: unknown namespace prefix {prefix}"));
+    }
+    match Factory::new().build(expr) {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(format!("{expr}: empty expression")),
+        Err(e) => return Err(format!("{expr}: {e}")),
+    }
+    // Unknown functions and wrong arguments are only found by the evaluation
+    let package = Package::new();
+    evaluate(&package.as_document(), expr)
+        .map(|_| ())
+        .map_err(|e| format!("{expr}: {e}"))
+}
+
+// Prefixes usable in the expressions, for attributes like xml:lang or xlink:href
+const NAMESPACES: [(&str, &str); 2] = [
+    ("xml", "http://www.w3.org/XML/1998/namespace"),
+    ("xlink", "http://www.w3.org/1999/xlink"),
+];
+
+// Prefixes of the names in the expression, outside of the strings and the axes like
+// child::. Unknown prefixes make the evaluation panic so they are checked first
+fn prefixes(expr: &str) -> Vec<&str> {
+    let mut prefixes = Vec::new();
+    let mut quote = None;
+    let bytes = expr.as_bytes();
+    for (i, &c) in bytes.iter().enumerate() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(c),
+            (None, b':') if bytes.get(i + 1) != Some(&b':') && i > 0 && bytes[i - 1] != b':' => {
+                let start = expr[..i]
+                    .rfind(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+                    .map_or(0, |start| start + 1);
+                if start < i {
+                    prefixes.push(&expr[start..i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    prefixes
+}
+
+// Copy of the html tree that can be queried with XPath. Elements and attributes
+// are not in a namespace so expressions like //div[@id] work as in a browser
+pub fn xml_document(page: &Html) -> Package {
+    fn copy<'a>(document: &Document<'a>, element: ElementRef) -> XmlElement<'a> {
+        let copied = document.create_element(&*element.value().name.local);
+        for (name, value) in &element.value().attrs {
+            // The prefix is kept in the name of the attributes outside of svg and math
+            let (prefix, local) = match &name.prefix {
+                Some(prefix) => (Some(&**prefix), &*name.local),
+                None => match name.local.split_once(':') {
+                    Some((prefix, local)) => (Some(prefix), local),
+                    None => (None, &*name.local),
+                },
+            };
+            let namespace = NAMESPACES
+                .iter()
+                .find(|(known, _)| Some(*known) == prefix)
+                .map(|(_, uri)| *uri);
+            match namespace {
+                Some(uri) => {
+                    copied.set_attribute_value(QName::with_namespace_uri(Some(uri), local), value)
+                }
+                None => copied.set_attribute_value(&*name.local, value),
+            };
+        }
+        for child in element.children() {
+            match child.value() {
+                scraper::Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        copied.append_child(copy(document, child));
+                    }
+                }
+                scraper::Node::Text(text) => copied.append_child(document.create_text(text)),
+                scraper::Node::Comment(comment) => {
+                    copied.append_child(document.create_comment(comment))
+                }
+                _ => {}
+            }
+        }
+        copied
+    }
+
+    let package = Package::new();
+    let document = package.as_document();
+    document
+        .root()
+        .append_child(copy(&document, page.root_element()));
+    package
+}
+
+// Values of the expression on the copy of the page. Nodes give their text, attributes
+// their value, and other results like count() a single value
+pub fn evaluate(document: &Document, expr: &str) -> Result<Vec<String>, String> {
+    let mut context = Context::new();
+    for (prefix, uri) in NAMESPACES {
+        context.set_namespace(prefix, uri);
+    }
+    let compiled = Factory::new()
+        .build(expr)
+        .map_err(|e| e.to_string())?
+        .ok_or("empty expression")?;
+    match compiled.evaluate(&context, document.root()) {
+        Ok(Value::Nodeset(nodes)) => Ok(nodes
+            .document_order()
+            .iter()
+            .map(|node| match node {
+                xpath::nodeset::Node::Attribute(attribute) => attribute.value().to_owned(),
+                node => node
+                    .string_value()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            })
+            .collect()),
+        Ok(value) => Ok(vec![value.string()]),
+        Err(e) => Err(readable_names(&e.to_string())),
+    }
+}
+
+// Names of the unknown functions and variables are given in their debug form
+fn readable_names(error: &str) -> String {
+    static NAME: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(
+            r#"OwnedPrefixedName \{ prefix: (?:None|Some\("([^"]*)"\)), local_part: "([^"]*)" \}"#,
+        )
+        .unwrap()
+    });
+    NAME.replace_all(error, |captures: &regex::Captures| match captures.get(1) {
+        Some(prefix) => format!("{}:{}", prefix.as_str(), &captures[2]),
+        None => captures[2].to_owned(),
+    })
+    .into_owned()
+}
+
+// Values of the XPath expressions, by name
+pub fn extract_xpaths(node: &Arc<Mutex<node::Node>>, page: &Html, xpaths: &[XPathSelection]) {
+    if xpaths.is_empty() {
+        return;
+    }
+    let package = xml_document(page);
+    let document = package.as_document();
+    let mut node = node.lock().unwrap();
+    for xpath in xpaths {
+        match evaluate(&document, &xpath.expr) {
+            Ok(values) => node
+                .extracted
+                .entry(xpath.name.clone())
+                .or_default()
+                .extend(values),
+            // The page itself is fine, the expression is reported only once
+            Err(e) => report_xpath(&xpath.expr, &e),
+        }
+    }
+}
+
+// Expressions whose evaluation already failed on a page
+static FAILED_XPATHS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub fn report_xpath(expr: &str, error: &str) {
+    let mut failed = FAILED_XPATHS.lock().unwrap();
+    if !failed.iter().any(|failed| failed == expr) {
+        eprintln!("{} {}: {}", "XPath error".red(), expr, error);
+        failed.push(expr.to_owned());
+    }
 }
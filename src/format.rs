@@ -1,6 +1,7 @@
 use url::Url;
 
 use crate::cli::{Content, Display, Format};
+use crate::download::ImageFile;
 
 use super::node::Node;
 
@@ -10,14 +11,13 @@ impl Node {
         contents: &Vec<Content>,
         cmd: &Display,
     ) -> std::result::Result<(), FormatError> {
-        let format = match cmd {
-            Display::Print { format } | Display::Save { format, .. } => format,
-            _ => return Err(FormatError::Graph),
-        };
-
-        match format {
-            Format::Json => Node::aggregate_json(node, contents),
-            Format::Raw => Node::aggregate_raw(node, contents),
+        match cmd.format() {
+            Some(Format::Json) => Node::aggregate_json(node, contents),
+            Some(Format::Raw) => Node::aggregate_raw(node, contents),
+            // Made once for the whole tree
+            Some(Format::Report | Format::Template | Format::Tree | Format::Snapshot) => Ok(()),
+            // Other commands use the raw data of the node
+            None => Ok(()),
         }
     }
 
@@ -29,6 +29,9 @@ impl Node {
         for content in contents {
             datas.append(&mut Self::format_json(node, content));
         }
+        datas.append(&mut selections_json(node));
+        datas.append(&mut matches_json(node));
+        datas.append(&mut records_json(node));
         node.output = serde_json::to_string(&datas).ok();
         Ok(())
     }
@@ -41,23 +44,38 @@ impl Node {
         for content in contents {
             datas.append(&mut Self::format_raw(node, content))
         }
+        datas.append(&mut selections_raw(node));
+        datas.append(&mut matches_raw(node));
+        datas.append(&mut records_raw(node));
         node.output = Some(datas.join("\n"));
         Ok(())
     }
 
     fn format_raw(node: &mut Node, content: &Content) -> Vec<String> {
         match content {
-            Content::Texts => node.texts.take().unwrap(),
-            Content::Comments => node.comments.take().unwrap(),
-            Content::Links => urls_string(node.links.take().unwrap()),
-            Content::Images => urls_string(node.images.take().unwrap()),
-            Content::Inputs => node.inputs.take().unwrap(),
+            Content::Texts => node.texts.take().unwrap_or_default(),
+            Content::Visible => node.visible.take().unwrap_or_default(),
+            Content::Comments => node.comments.take().unwrap_or_default(),
+            Content::Links => urls_string(node.links.take().unwrap_or_default()),
+            Content::Images => images_raw(node),
+            Content::Inputs => node.inputs.take().unwrap_or_default(),
+            Content::Forms => forms_raw(node),
+            Content::Meta => meta_raw(node),
+            Content::Structured => structured_raw(node),
+            Content::Tables => tables_raw(node),
+            Content::Markdown => markdown_raw(node),
             Content::All => vec![
-                node.texts.take().unwrap(),
-                node.comments.take().unwrap(),
-                urls_string(node.links.take().unwrap()),
-                urls_string(node.images.take().unwrap()),
-                node.inputs.take().unwrap(),
+                node.texts.take().unwrap_or_default(),
+                node.visible.take().unwrap_or_default(),
+                node.comments.take().unwrap_or_default(),
+                urls_string(node.links.take().unwrap_or_default()),
+                images_raw(node),
+                node.inputs.take().unwrap_or_default(),
+                forms_raw(node),
+                meta_raw(node),
+                structured_raw(node),
+                tables_raw(node),
+                markdown_raw(node),
             ]
             .into_iter()
             .flatten()
@@ -67,19 +85,39 @@ impl Node {
 
     fn format_json(node: &mut Node, content: &Content) -> Vec<Data> {
         match content {
-            Content::Texts => Data::json(node.texts.take().unwrap(), Content::Texts),
-            Content::Comments => Data::json(node.comments.take().unwrap(), Content::Comments),
-            Content::Links => Data::json(urls_string(node.links.take().unwrap()), Content::Links),
-            Content::Images => {
-                Data::json(urls_string(node.images.take().unwrap()), Content::Images)
+            Content::Texts => Data::json(node.texts.take().unwrap_or_default(), Content::Texts),
+            Content::Visible => {
+                Data::json(node.visible.take().unwrap_or_default(), Content::Visible)
             }
-            Content::Inputs => Data::json(node.inputs.take().unwrap(), Content::Inputs),
+            Content::Comments => {
+                Data::json(node.comments.take().unwrap_or_default(), Content::Comments)
+            }
+            Content::Links => Data::json(
+                urls_string(node.links.take().unwrap_or_default()),
+                Content::Links,
+            ),
+            Content::Images => images_json(node),
+            Content::Inputs => Data::json(node.inputs.take().unwrap_or_default(), Content::Inputs),
+            Content::Forms => forms_json(node),
+            Content::Meta => meta_json(node),
+            Content::Structured => structured_json(node),
+            Content::Tables => tables_json(node),
+            Content::Markdown => markdown_json(node),
             Content::All => vec![
-                Data::json(node.texts.take().unwrap(), Content::Texts),
-                Data::json(node.comments.take().unwrap(), Content::Comments),
-                Data::json(urls_string(node.links.take().unwrap()), Content::Links),
-                Data::json(urls_string(node.images.take().unwrap()), Content::Images),
-                Data::json(node.inputs.take().unwrap(), Content::Inputs),
+                Data::json(node.texts.take().unwrap_or_default(), Content::Texts),
+                Data::json(node.visible.take().unwrap_or_default(), Content::Visible),
+                Data::json(node.comments.take().unwrap_or_default(), Content::Comments),
+                Data::json(
+                    urls_string(node.links.take().unwrap_or_default()),
+                    Content::Links,
+                ),
+                images_json(node),
+                Data::json(node.inputs.take().unwrap_or_default(), Content::Inputs),
+                forms_json(node),
+                meta_json(node),
+                structured_json(node),
+                tables_json(node),
+                markdown_json(node),
             ]
             .into_iter()
             .flatten()
@@ -92,10 +130,229 @@ fn urls_string(urls: Vec<Url>) -> Vec<String> {
     urls.into_iter().map(|link| link.to_string()).collect()
 }
 
+// Downloaded images come with their local copy
+fn images_raw(node: &mut Node) -> Vec<String> {
+    match node.image_files.take() {
+        Some(files) => files.iter().map(ImageFile::to_string).collect(),
+        None => urls_string(node.images.take().unwrap_or_default()),
+    }
+}
+
+fn images_json(node: &mut Node) -> Vec<Data> {
+    match node.image_files.take() {
+        Some(files) => files
+            .into_iter()
+            .map(|file| Data {
+                r#type: Type::Content(Content::Images),
+                name: None,
+                content: serde_json::to_value(file).unwrap_or_default(),
+            })
+            .collect(),
+        None => Data::json(
+            urls_string(node.images.take().unwrap_or_default()),
+            Content::Images,
+        ),
+    }
+}
+
+fn forms_raw(node: &mut Node) -> Vec<String> {
+    node.forms
+        .take()
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|form| form.lines())
+        .collect()
+}
+
+fn forms_json(node: &mut Node) -> Vec<Data> {
+    node.forms
+        .take()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|form| Data {
+            r#type: Type::Content(Content::Forms),
+            name: None,
+            content: serde_json::to_value(form).unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn meta_raw(node: &mut Node) -> Vec<String> {
+    node.meta
+        .take()
+        .map(|meta| meta.lines())
+        .unwrap_or_default()
+}
+
+// The metadata is a single object
+fn meta_json(node: &mut Node) -> Vec<Data> {
+    node.meta
+        .take()
+        .map(|meta| Data {
+            r#type: Type::Content(Content::Meta),
+            name: None,
+            content: serde_json::to_value(meta).unwrap_or_default(),
+        })
+        .into_iter()
+        .collect()
+}
+
+fn structured_raw(node: &mut Node) -> Vec<String> {
+    node.structured
+        .take()
+        .map(|structured| structured.lines())
+        .unwrap_or_default()
+}
+
+fn structured_json(node: &mut Node) -> Vec<Data> {
+    node.structured
+        .take()
+        .map(|structured| Data {
+            r#type: Type::Content(Content::Structured),
+            name: None,
+            content: serde_json::to_value(structured).unwrap_or_default(),
+        })
+        .into_iter()
+        .collect()
+}
+
+// The tables are kept to be saved in csv files, and separated by an empty line
+fn tables_raw(node: &mut Node) -> Vec<String> {
+    let mut lines = Vec::new();
+    for table in node.tables.iter().flatten() {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(table.lines());
+    }
+    lines
+}
+
+fn tables_json(node: &mut Node) -> Vec<Data> {
+    node.tables
+        .iter()
+        .flatten()
+        .map(|table| Data {
+            r#type: Type::Content(Content::Tables),
+            name: None,
+            content: serde_json::to_value(table).unwrap_or_default(),
+        })
+        .collect()
+}
+
+// The markdown is kept to be saved in its own file
+fn markdown_raw(node: &mut Node) -> Vec<String> {
+    node.markdown
+        .iter()
+        .map(|markdown| markdown.document())
+        .collect()
+}
+
+fn markdown_json(node: &mut Node) -> Vec<Data> {
+    node.markdown
+        .iter()
+        .map(|markdown| Data {
+            r#type: Type::Content(Content::Markdown),
+            name: None,
+            content: serde_json::to_value(markdown).unwrap_or_default(),
+        })
+        .collect()
+}
+
+// Values of the selections are prefixed by their name
+fn selections_raw(node: &mut Node) -> Vec<String> {
+    std::mem::take(&mut node.extracted)
+        .into_iter()
+        .flat_map(|(name, values)| {
+            values
+                .into_iter()
+                .map(move |value| format!("{name}: {value}"))
+        })
+        .collect()
+}
+
+fn selections_json(node: &mut Node) -> Vec<Data> {
+    std::mem::take(&mut node.extracted)
+        .into_iter()
+        .flat_map(|(name, values)| {
+            values.into_iter().map(move |value| Data {
+                r#type: Type::Extraction(Extraction::Selection),
+                name: Some(name.clone()),
+                content: value.into(),
+            })
+        })
+        .collect()
+}
+
+// Matches of the regexes with their context
+fn matches_raw(node: &mut Node) -> Vec<String> {
+    std::mem::take(&mut node.matches)
+        .into_iter()
+        .map(|found| format!("{}: {} ({})", found.name, found.value, found.context))
+        .collect()
+}
+
+fn matches_json(node: &mut Node) -> Vec<Data> {
+    std::mem::take(&mut node.matches)
+        .into_iter()
+        .map(|found| Data {
+            r#type: Type::Extraction(Extraction::Match),
+            name: Some(found.name.clone()),
+            content: serde_json::to_value(found).unwrap_or_default(),
+        })
+        .collect()
+}
+
+// Records of the rules, the fields are written as json in the raw format
+fn records_raw(node: &mut Node) -> Vec<String> {
+    std::mem::take(&mut node.records)
+        .into_iter()
+        .map(|record| {
+            let mut line = format!(
+                "{}: {}",
+                record.name,
+                serde_json::to_string(&record.fields).unwrap_or_default()
+            );
+            if !record.errors.is_empty() {
+                line.push_str(&format!(" (errors: {})", record.errors.join(", ")));
+            }
+            line
+        })
+        .collect()
+}
+
+fn records_json(node: &mut Node) -> Vec<Data> {
+    std::mem::take(&mut node.records)
+        .into_iter()
+        .map(|record| Data {
+            r#type: Type::Extraction(Extraction::Record),
+            name: Some(record.name.clone()),
+            content: serde_json::to_value(record).unwrap_or_default(),
+        })
+        .collect()
+}
+
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+enum Type {
+    Content(Content),
+    Extraction(Extraction),
+}
+
+// Data asked with the extraction options, with its name
+#[derive(serde::Serialize, Clone, Copy)]
+enum Extraction {
+    Selection,
+    Match,
+    Record,
+}
+
 #[derive(serde::Serialize)]
 struct Data {
-    r#type: Content,
-    content: String,
+    r#type: Type,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    content: serde_json::Value,
 }
 
 impl Data {
@@ -103,8 +360,9 @@ impl Data {
         datas
             .into_iter()
             .map(|data| Data {
-                r#type: content,
-                content: data,
+                r#type: Type::Content(content),
+                name: None,
+                content: data.into(),
             })
             .collect()
     }
@@ -116,7 +374,6 @@ use std::fmt;
 #[derive(Debug)]
 pub enum FormatError {
     Serde,
-    Graph,
 }
 
 impl From<serde_json::Error> for FormatError {
@@ -0,0 +1,240 @@
+use markup5ever::local_name;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+use std::sync::{Arc, LazyLock, Mutex};
+use url::Url;
+
+use crate::extract::element_text;
+use crate::node::Node;
+
+// Names usually given to the anti-CSRF tokens
+static CSRF_NAME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)csrf|xsrf|authenticity|requestverification|nonce|(^|[_-])token$|^_?token")
+        .unwrap()
+});
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Form {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    // Url where the form is sent, the page itself by default
+    pub action: Option<String>,
+    pub method: String,
+    pub enctype: String,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Field {
+    pub tag: String,
+    pub name: Option<String>,
+    pub r#type: String,
+    pub id: Option<String>,
+    pub label: Option<String>,
+    // Value sent when the form is submitted as it is
+    pub value: Option<String>,
+    pub placeholder: Option<String>,
+    pub required: bool,
+    pub hidden: bool,
+    // Hidden field looking like an anti-CSRF token
+    pub csrf: bool,
+    pub options: Vec<FieldOption>,
+}
+
+// Option of a select
+#[derive(Serialize, Debug, Clone)]
+pub struct FieldOption {
+    pub value: String,
+    pub text: String,
+    pub selected: bool,
+}
+
+fn attr(element: ElementRef, name: &str) -> Option<String> {
+    element.value().attr(name).map(str::to_owned)
+}
+
+// Long random value, like most of the tokens
+fn random(value: &str) -> bool {
+    value.len() >= 16
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '=' | '_' | '-' | '.'))
+        && value.chars().any(|c| c.is_ascii_digit())
+        && value.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+impl Field {
+    fn from_element(element: ElementRef, page: &Html) -> Option<Self> {
+        let tag = element.value().name.local.clone();
+        let (kind, value, options) = match tag {
+            local_name!("input") => {
+                let kind = attr(element, "type")
+                    .map(|kind| kind.trim().to_lowercase())
+                    .unwrap_or("text".to_owned());
+                // Unchecked boxes aren't sent
+                let checkable = matches!(kind.as_str(), "checkbox" | "radio");
+                let value = match checkable && element.value().attr("checked").is_none() {
+                    true => None,
+                    false => attr(element, "value"),
+                };
+                (kind, value, Vec::new())
+            }
+            local_name!("textarea") => (
+                "textarea".to_owned(),
+                Some(element_text(element)),
+                Vec::new(),
+            ),
+            local_name!("button") => (
+                attr(element, "type")
+                    .map(|kind| kind.trim().to_lowercase())
+                    .unwrap_or("submit".to_owned()),
+                attr(element, "value"),
+                Vec::new(),
+            ),
+            local_name!("select") => {
+                let multiple = element.value().attr("multiple").is_some();
+                let option = Selector::parse("option").unwrap();
+                let options: Vec<FieldOption> = element
+                    .select(&option)
+                    .map(|option| FieldOption {
+                        value: attr(option, "value").unwrap_or_else(|| element_text(option)),
+                        text: element_text(option),
+                        selected: option.value().attr("selected").is_some(),
+                    })
+                    .collect();
+                // Without a selected option, the first one is sent
+                let value = options
+                    .iter()
+                    .find(|option| option.selected)
+                    .or(options.first().filter(|_| !multiple))
+                    .map(|option| option.value.clone());
+                let kind = if multiple {
+                    "select-multiple"
+                } else {
+                    "select-one"
+                };
+                (kind.to_owned(), value, options)
+            }
+            _ => return None,
+        };
+
+        let id = attr(element, "id");
+        // Label pointing at the field or wrapping it
+        let label = id
+            .as_ref()
+            .and_then(|id| {
+                page.select(&Selector::parse("label[for]").unwrap())
+                    .find(|label| label.value().attr("for") == Some(id))
+            })
+            .or_else(|| {
+                element
+                    .ancestors()
+                    .filter_map(ElementRef::wrap)
+                    .find(|parent| parent.value().name.local == local_name!("label"))
+            })
+            .map(element_text)
+            .filter(|label| !label.is_empty());
+        let name = attr(element, "name");
+        let hidden = kind == "hidden" || element.value().attr("hidden").is_some();
+        let csrf = kind == "hidden"
+            && (name.as_deref().is_some_and(|name| CSRF_NAME.is_match(name))
+                || value.as_deref().is_some_and(random));
+        Some(Field {
+            tag: tag.to_string(),
+            name,
+            r#type: kind,
+            id,
+            label,
+            value,
+            placeholder: attr(element, "placeholder"),
+            required: element.value().attr("required").is_some(),
+            hidden,
+            csrf,
+            options,
+        })
+    }
+
+    // The field on a single line with its flags
+    fn line(&self) -> String {
+        let mut line = format!("  {} {}", self.tag, self.r#type);
+        if let Some(name) = &self.name {
+            line.push_str(&format!(" name={name}"));
+        }
+        if let Some(value) = &self.value {
+            line.push_str(&format!(" value={value}"));
+        }
+        let flags = [
+            ("required", self.required),
+            ("hidden", self.hidden),
+            ("csrf", self.csrf),
+        ];
+        for (flag, set) in flags {
+            if set {
+                line.push_str(&format!(" {flag}"));
+            }
+        }
+        if !self.options.is_empty() {
+            let options: Vec<&str> = self.options.iter().map(|o| o.value.as_str()).collect();
+            line.push_str(&format!(" options={}", options.join("|")));
+        }
+        line
+    }
+}
+
+impl Form {
+    fn from_element(form: ElementRef, page: &Html, url: &Url) -> Self {
+        let id = attr(form, "id");
+        let fields = Selector::parse("input, select, textarea, button").unwrap();
+        // Fields of the form, and the fields outside of it linked by their form attribute
+        let fields = page
+            .select(&fields)
+            .filter(|field| match field.value().attr("form") {
+                Some(owner) => id.as_deref() == Some(owner),
+                None => field.ancestors().any(|parent| parent.id() == form.id()),
+            })
+            .filter_map(|field| Field::from_element(field, page))
+            .collect();
+        let action = form
+            .value()
+            .attr("action")
+            .map(str::trim)
+            .filter(|action| !action.is_empty())
+            .map_or(Some(url.clone()), |action| Url::join(url, action).ok());
+        Form {
+            name: attr(form, "name"),
+            id,
+            action: action.map(|action| action.to_string()),
+            method: attr(form, "method")
+                .map(|method| method.trim().to_lowercase())
+                .filter(|method| matches!(method.as_str(), "get" | "post" | "dialog"))
+                .unwrap_or("get".to_owned()),
+            enctype: attr(form, "enctype")
+                .map(|enctype| enctype.trim().to_lowercase())
+                .unwrap_or("application/x-www-form-urlencoded".to_owned()),
+            fields,
+        }
+    }
+
+    // The form followed by its fields
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "form {} {} ({})",
+            self.method,
+            self.action.as_deref().unwrap_or_default(),
+            self.enctype
+        )];
+        lines.extend(self.fields.iter().map(Field::line));
+        lines
+    }
+}
+
+pub fn extract_forms(node: &Arc<Mutex<Node>>, page: &Html) {
+    let mut node = node.lock().unwrap();
+    let forms = Selector::parse("form").unwrap();
+    node.forms = Some(
+        page.select(&forms)
+            .map(|form| Form::from_element(form, page, &node.url))
+            .collect(),
+    );
+}
@@ -1,31 +1,72 @@
 use askama::Template;
 use colored::Colorize;
 use serde::Serialize;
-use std::{collections::HashSet, fmt, fs};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::{self, Write},
+    fs,
+    path::Path,
+};
+use url::Url;
 
+use crate::cli::{GraphFormat, GraphMetric};
+use crate::extract::{Link, Position};
+use crate::hierarchy::{self, Branch};
+use crate::meta::Meta;
+use crate::metrics::{self, Metrics};
 use crate::node::Node;
 
 #[derive(Template)]
 #[template(path = "index.html")]
 struct GraphTemplate {
     graph: Graph,
+    // Renderer bundled in the binary so the page works offline
+    script: &'static str,
+    // Metric selected when the page opens
+    metric: Option<GraphMetric>,
+    hierarchy: Vec<Branch>,
 }
 
+// Sets are ordered so the exports are the same between two runs
 #[derive(Serialize, Debug, Clone)]
 struct Graph {
-    nodes: HashSet<GraphNode>,
-    edges: HashSet<GraphEdge>,
+    nodes: BTreeSet<GraphNode>,
+    // Every link between two crawled pages
+    edges: BTreeSet<GraphEdge>,
+    // Page from which each page was discovered, a BFS spanning tree of the crawl
+    tree: BTreeSet<TreeEdge>,
 }
 
-// TODO: If i add more information, it could be great to implement
-// (PartialEq, Eq, Hash) to have accurate hashset
-#[derive(Serialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Serialize, Debug, Clone)]
 struct GraphNode {
     id: String,
     label: String,
     images: Vec<String>,
     comments: Vec<String>,
     inputs: Vec<String>,
+    meta: Option<Meta>,
+    metrics: Metrics,
+}
+
+// A node is identified by its id, the metrics can't be compared
+impl PartialEq for GraphNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for GraphNode {}
+
+impl PartialOrd for GraphNode {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for GraphNode {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.id.cmp(&other.id)
+    }
 }
 
 impl GraphNode {
@@ -42,61 +83,407 @@ impl GraphNode {
                 .collect(),
             comments: node.comments.as_deref().unwrap_or_default().to_vec(),
             inputs: node.inputs.as_deref().unwrap_or_default().to_vec(),
+            meta: node.meta.clone(),
+            metrics: Metrics::default(),
         }
     }
 }
 
-#[derive(Serialize, Debug, Clone, Eq, PartialEq, Hash)]
+// Directed edge, every link from a page to the other is part of the same edge
+#[derive(Serialize, Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
 struct GraphEdge {
     from: String,
     to: String,
+    weight: usize,
+    texts: Vec<String>,
+    rels: Vec<String>,
+    positions: Vec<Position>,
+    // The page `to` was discovered from `from`
+    tree: bool,
+}
+
+impl GraphEdge {
+    fn new(from: &str, to: &str, links: &[&Link], tree: bool) -> Self {
+        let unique = |values: Vec<String>| {
+            let mut values: Vec<String> = values.into_iter().filter(|v| !v.is_empty()).collect();
+            values.sort();
+            values.dedup();
+            values
+        };
+        let mut positions: Vec<Position> = links.iter().map(|link| link.position).collect();
+        positions.sort();
+        positions.dedup();
+        Self {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            weight: links.len(),
+            texts: unique(links.iter().map(|link| link.text.clone()).collect()),
+            rels: unique(links.iter().flat_map(|link| link.rel.clone()).collect()),
+            positions,
+            tree,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+struct TreeEdge {
+    from: String,
+    to: String,
 }
 
 impl Graph {
     fn from_root(node: &Node) -> Self {
-        let (mut nodes, mut edges) = (HashSet::<GraphNode>::new(), HashSet::<GraphEdge>::new());
-        let graph_child = Graph::by_children(node);
-        nodes.extend(graph_child.nodes);
-        edges.extend(graph_child.edges);
-        Graph { nodes, edges }
+        let mut graph = Graph {
+            nodes: BTreeSet::new(),
+            edges: BTreeSet::new(),
+            tree: BTreeSet::new(),
+        };
+        let mut pages: Vec<(String, Vec<Link>)> = Vec::new();
+        let mut ids: HashMap<Url, String> = HashMap::new();
+        graph.by_children(node, &mut pages, &mut ids);
+
+        // A page already visited isn't a child anymore, the links must be
+        // found back with the url
+        for (from, anchors) in &pages {
+            let mut targets: HashMap<&String, Vec<&Link>> = HashMap::new();
+            for link in anchors {
+                match ids.get(&link.url) {
+                    Some(to) if to != from => targets.entry(to).or_default().push(link),
+                    _ => {}
+                }
+            }
+            for (to, links) in targets {
+                let tree = graph.tree.contains(&TreeEdge {
+                    from: from.clone(),
+                    to: to.clone(),
+                });
+                graph.edges.insert(GraphEdge::new(from, to, &links, tree));
+            }
+        }
+        graph.analyze(&node.id);
+        graph
     }
 
-    fn by_children(node: &Node) -> Self {
-        let (mut nodes, mut edges) = (
-            HashSet::from([GraphNode::from_node(node)]),
-            HashSet::<GraphEdge>::new(),
-        );
+    fn analyze(&mut self, seed: &str) {
+        let nodes: Vec<GraphNode> = std::mem::take(&mut self.nodes).into_iter().collect();
+        let index: HashMap<&str, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.id.as_str(), i))
+            .collect();
+        let edges: Vec<(usize, usize)> = self
+            .edges
+            .iter()
+            .filter_map(|edge| {
+                Some((
+                    *index.get(edge.from.as_str())?,
+                    *index.get(edge.to.as_str())?,
+                ))
+            })
+            .collect();
+        let metrics = metrics::compute(nodes.len(), &edges, index[seed]);
+        self.nodes = nodes
+            .into_iter()
+            .zip(metrics)
+            .map(|(node, metrics)| GraphNode { metrics, ..node })
+            .collect();
+    }
+
+    fn by_children(
+        &mut self,
+        node: &Node,
+        pages: &mut Vec<(String, Vec<Link>)>,
+        ids: &mut HashMap<Url, String>,
+    ) {
+        self.nodes.insert(GraphNode::from_node(node));
+        pages.push((node.id.clone(), node.anchors.clone()));
+        ids.insert(node.url.clone(), node.id.clone());
         for child in node.children.clone() {
-            if !child.lock().unwrap().explored {
+            let child = child.lock().unwrap();
+            if !child.explored {
                 continue;
             }
-            edges.insert(GraphEdge {
+            self.tree.insert(TreeEdge {
                 from: node.id.clone(),
-                to: child.lock().unwrap().id.clone(),
+                to: child.id.clone(),
             });
-            let graph = Graph::by_children(&child.lock().unwrap());
-            nodes.extend(graph.nodes);
-            edges.extend(graph.edges);
+            self.by_children(&child, pages, ids);
         }
-        Graph { nodes, edges }
     }
 }
 
+impl Graph {
+    fn dot(&self) -> String {
+        let quote = |text: &str| {
+            format!(
+                "\"{}\"",
+                text.replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n")
+            )
+        };
+        let mut dot = String::from("digraph coma {\n");
+        for node in &self.nodes {
+            let _ = writeln!(
+                dot,
+                "  {} [label={}, images={}, comments={}, inputs={}];",
+                quote(&node.id),
+                quote(&node.label),
+                node.images.len(),
+                node.comments.len(),
+                node.inputs.len()
+            );
+        }
+        for edge in &self.edges {
+            let _ = writeln!(
+                dot,
+                "  {} -> {} [weight={}, penwidth={}, style={}, texts={}, rels={}, positions={}];",
+                quote(&edge.from),
+                quote(&edge.to),
+                edge.weight,
+                edge.weight.min(8),
+                if edge.tree { "solid" } else { "dashed" },
+                quote(&edge.texts.join(", ")),
+                quote(&edge.rels.join(", ")),
+                quote(&positions(&edge.positions)),
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn graphml(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        );
+        let keys = [
+            ("label", "node", "string"),
+            ("images", "node", "int"),
+            ("comments", "node", "int"),
+            ("inputs", "node", "int"),
+            ("weight", "edge", "int"),
+            ("texts", "edge", "string"),
+            ("rels", "edge", "string"),
+            ("positions", "edge", "string"),
+            ("tree", "edge", "boolean"),
+        ];
+        for (id, domain, kind) in keys {
+            let _ = writeln!(
+                xml,
+                "  <key id=\"{id}\" for=\"{domain}\" attr.name=\"{id}\" attr.type=\"{kind}\"/>"
+            );
+        }
+        xml.push_str("  <graph id=\"coma\" edgedefault=\"directed\">\n");
+        for node in &self.nodes {
+            let _ = writeln!(xml, "    <node id=\"{}\">", escape(&node.id));
+            let _ = writeln!(
+                xml,
+                "      <data key=\"label\">{}</data>\n\
+                 \x20     <data key=\"images\">{}</data>\n\
+                 \x20     <data key=\"comments\">{}</data>\n\
+                 \x20     <data key=\"inputs\">{}</data>",
+                escape(&node.label),
+                node.images.len(),
+                node.comments.len(),
+                node.inputs.len()
+            );
+            xml.push_str("    </node>\n");
+        }
+        for edge in &self.edges {
+            let _ = writeln!(
+                xml,
+                "    <edge source=\"{}\" target=\"{}\">",
+                escape(&edge.from),
+                escape(&edge.to)
+            );
+            let _ = writeln!(
+                xml,
+                "      <data key=\"weight\">{}</data>\n\
+                 \x20     <data key=\"texts\">{}</data>\n\
+                 \x20     <data key=\"rels\">{}</data>\n\
+                 \x20     <data key=\"positions\">{}</data>\n\
+                 \x20     <data key=\"tree\">{}</data>",
+                edge.weight,
+                escape(&edge.texts.join(", ")),
+                escape(&edge.rels.join(", ")),
+                positions(&edge.positions),
+                edge.tree
+            );
+            xml.push_str("    </edge>\n");
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    fn gexf(&self) -> String {
+        let mut xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <gexf xmlns=\"http://gexf.net/1.3\" version=\"1.3\">\n\
+             \x20 <meta lastmodifieddate=\"{}\">\n\
+             \x20   <creator>coma {}</creator>\n\
+             \x20 </meta>\n\
+             \x20 <graph defaultedgetype=\"directed\" mode=\"static\">\n\
+             \x20   <attributes class=\"node\">\n\
+             \x20     <attribute id=\"images\" title=\"images\" type=\"integer\"/>\n\
+             \x20     <attribute id=\"comments\" title=\"comments\" type=\"integer\"/>\n\
+             \x20     <attribute id=\"inputs\" title=\"inputs\" type=\"integer\"/>\n\
+             \x20   </attributes>\n\
+             \x20   <attributes class=\"edge\">\n\
+             \x20     <attribute id=\"texts\" title=\"texts\" type=\"string\"/>\n\
+             \x20     <attribute id=\"rels\" title=\"rels\" type=\"string\"/>\n\
+             \x20     <attribute id=\"positions\" title=\"positions\" type=\"string\"/>\n\
+             \x20     <attribute id=\"tree\" title=\"tree\" type=\"boolean\"/>\n\
+             \x20   </attributes>\n\
+             \x20   <nodes>\n",
+            chrono::Utc::now().format("%Y-%m-%d"),
+            env!("CARGO_PKG_VERSION")
+        );
+        for node in &self.nodes {
+            let _ = writeln!(
+                xml,
+                "      <node id=\"{}\" label=\"{}\">\n\
+                 \x20       <attvalues>\n\
+                 \x20         <attvalue for=\"images\" value=\"{}\"/>\n\
+                 \x20         <attvalue for=\"comments\" value=\"{}\"/>\n\
+                 \x20         <attvalue for=\"inputs\" value=\"{}\"/>\n\
+                 \x20       </attvalues>\n\
+                 \x20     </node>",
+                escape(&node.id),
+                escape(&node.label),
+                node.images.len(),
+                node.comments.len(),
+                node.inputs.len()
+            );
+        }
+        xml.push_str("    </nodes>\n    <edges>\n");
+        for (i, edge) in self.edges.iter().enumerate() {
+            let _ = writeln!(
+                xml,
+                "      <edge id=\"{i}\" source=\"{}\" target=\"{}\" weight=\"{}\">\n\
+                 \x20       <attvalues>\n\
+                 \x20         <attvalue for=\"texts\" value=\"{}\"/>\n\
+                 \x20         <attvalue for=\"rels\" value=\"{}\"/>\n\
+                 \x20         <attvalue for=\"positions\" value=\"{}\"/>\n\
+                 \x20         <attvalue for=\"tree\" value=\"{}\"/>\n\
+                 \x20       </attvalues>\n\
+                 \x20     </edge>",
+                escape(&edge.from),
+                escape(&edge.to),
+                edge.weight,
+                escape(&edge.texts.join(", ")),
+                escape(&edge.rels.join(", ")),
+                positions(&edge.positions),
+                edge.tree
+            );
+        }
+        xml.push_str("    </edges>\n  </graph>\n</gexf>\n");
+        xml
+    }
+
+    // The urls can't be used as mermaid ids, the nodes are numbered instead
+    fn mermaid(&self) -> String {
+        let label = |text: &str| text.replace('"', "#quot;");
+        let mut ids: HashMap<&str, usize> = HashMap::new();
+        let mut mermaid = String::from("flowchart LR\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            ids.insert(&node.id, i);
+            let _ = writeln!(mermaid, "  n{i}[\"{}\"]", label(&node.label));
+        }
+        for edge in &self.edges {
+            let (Some(from), Some(to)) = (ids.get(edge.from.as_str()), ids.get(edge.to.as_str()))
+            else {
+                continue;
+            };
+            let arrow = if edge.tree { "-->" } else { "-.->" };
+            let _ = writeln!(mermaid, "  n{from} {arrow}|{}| n{to}", edge.weight);
+        }
+        mermaid
+    }
+
+    fn html(self, metric: Option<GraphMetric>) -> Result<String, GraphError> {
+        let urls: Vec<Url> = self
+            .nodes
+            .iter()
+            .filter_map(|node| Url::parse(&node.label).ok())
+            .collect();
+        let template = GraphTemplate {
+            hierarchy: hierarchy::build(&urls),
+            graph: self,
+            script: include_str!("../static/graph.js"),
+            metric,
+        };
+        template.render().map_err(|e| GraphError(e.to_string()))
+    }
+}
+
+fn positions(positions: &[Position]) -> String {
+    positions
+        .iter()
+        .map(|position| match position {
+            Position::Nav => "nav",
+            Position::Body => "body",
+            Position::Footer => "footer",
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 // NOTE : we suppose we search from the root and thus we will never need to look at parents
 // If we want to support multiple root, we'll have to rethink this
-pub fn render(root: &Node) -> Result<(), GraphError> {
-    let template = GraphTemplate {
-        graph: Graph::from_root(root),
+pub fn render(
+    root: &Node,
+    format: GraphFormat,
+    output: Option<&Path>,
+    open: bool,
+    metric: Option<GraphMetric>,
+) -> Result<(), GraphError> {
+    let graph = Graph::from_root(root);
+    let content = match format {
+        GraphFormat::Html => graph.html(metric)?,
+        GraphFormat::Dot => graph.dot(),
+        GraphFormat::Graphml => graph.graphml(),
+        GraphFormat::Gexf => graph.gexf(),
+        GraphFormat::Mermaid => graph.mermaid(),
+        GraphFormat::Json => {
+            serde_json::to_string_pretty(&graph).map_err(|e| GraphError(e.to_string()))?
+        }
     };
-    let html = template.render().map_err(|e| GraphError(e.to_string()))?;
-    let mut temp_file_path = std::env::temp_dir();
-    temp_file_path.push(root.url.domain().unwrap().to_owned() + ".html");
-    fs::write(&temp_file_path, html).expect("Failed to write to named file");
-    let temp_file_path_str = temp_file_path.to_str().expect("Failed to get file path");
-    webbrowser::open(temp_file_path_str).expect("Failed to open in web browser");
+
+    let path = match output {
+        Some(path) => path.to_path_buf(),
+        None if format == GraphFormat::Html => {
+            let mut temp_file_path = std::env::temp_dir();
+            temp_file_path.push(root.url.domain().unwrap().to_owned() + ".html");
+            temp_file_path
+        }
+        None => {
+            println!("{content}");
+            return Ok(());
+        }
+    };
+    fs::write(&path, content).map_err(|e| GraphError(e.to_string()))?;
+    if format == GraphFormat::Html && open {
+        let path = path.to_str().expect("Failed to get file path");
+        webbrowser::open(path).expect("Failed to open in web browser");
+    } else {
+        println!("Graph written in {}", path.display());
+    }
     Ok(())
 }
 
+// Graph of the pages crawled so far, for the web interface
+pub fn json(root: &Node) -> Result<serde_json::Value, GraphError> {
+    serde_json::to_value(Graph::from_root(root)).map_err(|e| GraphError(e.to_string()))
+}
+
 pub struct GraphError(String);
 
 impl GraphError {
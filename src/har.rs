@@ -0,0 +1,330 @@
+use base64::Engine;
+use chrono::SecondsFormat;
+use colored::Colorize;
+use serde::Serialize;
+use std::{
+    fmt, fs,
+    sync::{Arc, Mutex},
+};
+use url::Url;
+
+use crate::network::{Exchange, Timings};
+use crate::node::Node;
+
+// HTTP Archive 1.2, see http://www.softwareishard.com/blog/har-12-spec/
+#[derive(Serialize)]
+struct Har {
+    log: Log,
+}
+
+#[derive(Serialize)]
+struct Log {
+    version: &'static str,
+    creator: Creator,
+    pages: Vec<Page>,
+    entries: Vec<Entry>,
+}
+
+#[derive(Serialize)]
+struct Creator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Page {
+    started_date_time: String,
+    id: String,
+    title: String,
+    page_timings: PageTimings,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PageTimings {
+    on_content_load: f64,
+    on_load: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    pageref: String,
+    started_date_time: String,
+    time: f64,
+    request: Request,
+    response: Response,
+    cache: Cache,
+    timings: Timings,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "serverIPAddress")]
+    server_ip_address: Option<String>,
+    #[serde(rename = "_resourceType")]
+    resource_type: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+    method: String,
+    url: String,
+    http_version: String,
+    cookies: Vec<NameValue>,
+    headers: Vec<NameValue>,
+    query_string: Vec<NameValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    post_data: Option<PostData>,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Response {
+    status: u32,
+    status_text: String,
+    http_version: String,
+    cookies: Vec<NameValue>,
+    headers: Vec<NameValue>,
+    content: Content,
+    #[serde(rename = "redirectURL")]
+    redirect_url: String,
+    headers_size: i64,
+    body_size: i64,
+    #[serde(rename = "_transferSize")]
+    transfer_size: f64,
+}
+
+#[derive(Serialize)]
+struct NameValue {
+    name: String,
+    value: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PostData {
+    mime_type: String,
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Content {
+    size: i64,
+    mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct Cache {}
+
+// Export the network traffic of every page, in one `<name>.har` or in one
+// `<name>-<n>.har` per page when `split`
+pub fn write(root: &Arc<Mutex<Node>>, name: &str, split: bool) -> Result<(), HarError> {
+    let mut pages: Vec<(Page, Vec<Entry>)> = Vec::new();
+    Node::explore(root, &mut |node: &mut Node| {
+        let exchanges = node.exchanges.take().unwrap_or_default();
+        let id = format!("page_{}", pages.len() + 1);
+        let started = exchanges
+            .iter()
+            .map(|exchange| exchange.date)
+            .min()
+            .unwrap_or_else(chrono::Utc::now);
+        let page = Page {
+            started_date_time: started.to_rfc3339_opts(SecondsFormat::Millis, true),
+            id: id.clone(),
+            title: node.url.to_string(),
+            page_timings: PageTimings {
+                on_content_load: -1.0,
+                on_load: -1.0,
+            },
+        };
+        let entries = exchanges
+            .into_iter()
+            .map(|exchange| entry(exchange, &id))
+            .collect();
+        pages.push((page, entries));
+        Ok(())
+    })
+    .map_err(|e| HarError::Message(e.to_string()))?;
+
+    if split {
+        for (index, (page, entries)) in pages.into_iter().enumerate() {
+            let file_name = format!("{}-{}.har", name, index + 1);
+            println!("Writing {} for {}", file_name, page.title.green());
+            save(&file_name, vec![page], entries)?;
+        }
+    } else {
+        let file_name = format!("{name}.har");
+        let (pages, entries): (Vec<Page>, Vec<Vec<Entry>>) = pages.into_iter().unzip();
+        println!(
+            "Writing {} for {} pages",
+            file_name,
+            pages.len().to_string().green()
+        );
+        save(&file_name, pages, entries.into_iter().flatten().collect())?;
+    }
+    Ok(())
+}
+
+fn save(file_name: &str, pages: Vec<Page>, entries: Vec<Entry>) -> Result<(), HarError> {
+    let har = Har {
+        log: Log {
+            version: "1.2",
+            creator: Creator {
+                name: "coma",
+                version: env!("CARGO_PKG_VERSION"),
+            },
+            pages,
+            entries,
+        },
+    };
+    let json = serde_json::to_string_pretty(&har).map_err(|e| HarError::Message(e.to_string()))?;
+    fs::write(file_name, json)?;
+    Ok(())
+}
+
+fn entry(exchange: Exchange, pageref: &str) -> Entry {
+    let http_version = match exchange.protocol.as_str() {
+        "h2" => "HTTP/2".to_owned(),
+        "h3" | "h3-29" => "HTTP/3".to_owned(),
+        protocol => protocol.to_uppercase(),
+    };
+    let timings = exchange.timings.unwrap_or(Timings {
+        blocked: -1.0,
+        dns: -1.0,
+        connect: -1.0,
+        ssl: -1.0,
+        send: 0.0,
+        wait: 0.0,
+        receive: 0.0,
+    });
+    let request_mime = header(&exchange.request_headers, "content-type").unwrap_or_default();
+
+    // Binary bodies are kept in base64
+    let (size, text, encoding) = match exchange.body {
+        Some(body) => {
+            let size = body.len() as i64;
+            match String::from_utf8(body) {
+                Ok(text) => (size, Some(text), None),
+                Err(e) => (
+                    size,
+                    Some(base64::engine::general_purpose::STANDARD.encode(e.into_bytes())),
+                    Some("base64"),
+                ),
+            }
+        }
+        None => (0, None, None),
+    };
+
+    Entry {
+        pageref: pageref.to_owned(),
+        started_date_time: exchange.date.to_rfc3339_opts(SecondsFormat::Millis, true),
+        time: timings.total(),
+        request: Request {
+            method: exchange.method,
+            query_string: Url::parse(&exchange.url)
+                .map(|url| {
+                    url.query_pairs()
+                        .map(|(name, value)| NameValue {
+                            name: name.into_owned(),
+                            value: value.into_owned(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            url: exchange.url,
+            http_version: http_version.clone(),
+            cookies: Vec::new(),
+            headers: name_values(&exchange.request_headers),
+            body_size: exchange
+                .post_data
+                .as_ref()
+                .map_or(0, |data| data.len() as i64),
+            post_data: exchange.post_data.map(|text| PostData {
+                mime_type: request_mime,
+                text,
+            }),
+            headers_size: -1,
+        },
+        response: Response {
+            status: exchange.status,
+            status_text: exchange.status_text,
+            http_version,
+            cookies: Vec::new(),
+            redirect_url: header(&exchange.response_headers, "location").unwrap_or_default(),
+            headers: name_values(&exchange.response_headers),
+            content: Content {
+                size,
+                mime_type: exchange.mime,
+                text,
+                encoding,
+            },
+            headers_size: -1,
+            body_size: -1,
+            transfer_size: exchange.transfer_size,
+        },
+        cache: Cache {},
+        timings,
+        server_ip_address: exchange.remote_ip,
+        resource_type: exchange.resource.to_lowercase(),
+    }
+}
+
+fn header(headers: &[(String, String)], name: &str) -> Option<String> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.clone())
+}
+
+// Repeated headers are joined with a newline by the browser
+fn name_values(headers: &[(String, String)]) -> Vec<NameValue> {
+    headers
+        .iter()
+        .flat_map(|(name, value)| {
+            value.split('\n').map(|value| NameValue {
+                name: name.clone(),
+                value: value.to_owned(),
+            })
+        })
+        .collect()
+}
+
+pub enum HarError {
+    IO(std::io::Error),
+    Message(String),
+}
+
+impl HarError {
+    fn print(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HarError::IO(e) => write!(f, "{}: {}", "Har error".red(), e),
+            HarError::Message(s) => write!(f, "{}: {}", "Har error".red(), s),
+        }
+    }
+}
+
+impl fmt::Display for HarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl fmt::Debug for HarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl std::error::Error for HarError {}
+
+impl From<std::io::Error> for HarError {
+    fn from(value: std::io::Error) -> Self {
+        HarError::IO(value)
+    }
+}
@@ -0,0 +1,107 @@
+use serde::Serialize;
+use std::{
+    error,
+    fmt::Write,
+    sync::{Arc, Mutex},
+};
+use url::Url;
+
+use crate::node::Node;
+
+// Segment of the url paths, the first level is the host
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Branch {
+    pub name: String,
+    // Pages crawled at exactly this path, several with different queries
+    pub urls: Vec<String>,
+    // Pages crawled in the whole branch
+    pub pages: usize,
+    pub children: Vec<Branch>,
+}
+
+impl Branch {
+    fn new(name: &str) -> Self {
+        Branch {
+            name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn insert(&mut self, segments: &[&str], url: &Url) {
+        self.pages += 1;
+        let Some((segment, rest)) = segments.split_first() else {
+            self.urls.push(url.to_string());
+            return;
+        };
+        let index = match self.children.iter().position(|c| c.name == *segment) {
+            Some(index) => index,
+            None => {
+                self.children.push(Branch::new(segment));
+                self.children.len() - 1
+            }
+        };
+        self.children[index].insert(rest, url);
+    }
+
+    fn sort(&mut self) {
+        self.children.sort_by(|a, b| a.name.cmp(&b.name));
+        self.children.iter_mut().for_each(Branch::sort);
+    }
+}
+
+// Tree of the url paths of the pages, independent of the links between them.
+// There is a root for every host crawled
+pub fn build<'a>(urls: impl IntoIterator<Item = &'a Url>) -> Vec<Branch> {
+    let mut hosts: Vec<Branch> = Vec::new();
+    for url in urls {
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_owned(),
+            (None, _) => url.scheme().to_owned(),
+        };
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+        let index = match hosts.iter().position(|h| h.name == host) {
+            Some(index) => index,
+            None => {
+                hosts.push(Branch::new(&host));
+                hosts.len() - 1
+            }
+        };
+        hosts[index].insert(&segments, url);
+    }
+    hosts.iter_mut().for_each(Branch::sort);
+    hosts
+}
+
+// Indented text tree with the number of pages of each branch
+pub fn render(root: &Arc<Mutex<Node>>) -> Result<String, Box<dyn error::Error>> {
+    let mut urls: Vec<Url> = Vec::new();
+    Node::explore(root, &mut |node: &mut Node| {
+        urls.push(node.url.clone());
+        Ok(())
+    })?;
+
+    let mut tree = String::new();
+    for host in build(&urls) {
+        writeln!(tree, "{} ({})", host.name, host.pages)?;
+        write_children(&mut tree, &host, "")?;
+    }
+    Ok(tree)
+}
+
+fn write_children(tree: &mut String, branch: &Branch, indent: &str) -> std::fmt::Result {
+    for (i, child) in branch.children.iter().enumerate() {
+        let last = i == branch.children.len() - 1;
+        let (prefix, next) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        writeln!(tree, "{indent}{prefix}{} ({})", child.name, child.pages)?;
+        write_children(tree, child, &format!("{indent}{next}"))?;
+    }
+    Ok(())
+}
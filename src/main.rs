@@ -1,4 +1,5 @@
 use std::{
+    collections::HashSet,
     error, process,
     sync::{Arc, Mutex},
 };
@@ -9,14 +10,36 @@ use tokio::{sync::Semaphore, task::JoinSet};
 mod browser;
 mod cli;
 mod config;
+mod diff;
 mod display;
+mod download;
 mod extract;
 mod format;
+mod forms;
 mod graph;
+mod har;
+mod hierarchy;
+mod markdown;
+mod meta;
+mod metrics;
+mod mirror;
+mod network;
 mod node;
+mod patterns;
+mod report;
+mod rules;
+mod serve;
+mod snapshot;
 mod state;
+mod structured;
+mod tables;
+mod template;
+mod text;
+mod warc;
+mod watch;
 
 use browser::Browser;
+use cli::{Content, Display, Format};
 use config::Config;
 use node::Node;
 use state::State;
@@ -24,15 +47,42 @@ use state::State;
 static PERMITS: Semaphore = Semaphore::const_new(0);
 
 async fn run() -> Result<(), Box<dyn std::error::Error>> {
-    let conf = Config::new()?;
+    let args = cli::args()?;
+    if let Display::Diff { old, new, json } = &args.cmd {
+        return diff::run(old, new, *json);
+    }
+    let mut conf = Config::new(args)?;
+    PERMITS.add_permits(conf.args.thread as usize);
+    if let Display::Watch { .. } = conf.args.cmd {
+        return watch::run(&mut conf).await;
+    }
+    // Started before the crawl to follow it live
+    let server = match &conf.args.cmd {
+        Display::Serve { port, address } => Some(serve::Server::start(&conf.root, address, *port)?),
+        _ => None,
+    };
+    crawl(&conf).await?;
+
+    println!("Formatting");
+    format(&conf)?;
+
+    println!("Displaying");
+    match server {
+        Some(server) => server.finished().await?,
+        None => display(&conf).await?,
+    }
+    Ok(())
+}
+
+// Explore the site from the root of the configuration
+async fn crawl(conf: &Config) -> Result<(), Box<dyn std::error::Error>> {
     let mut state = State::new(Arc::clone(&conf.root))?;
     println!("Crawling");
-    PERMITS.add_permits(conf.args.thread as usize);
     while state.pop_layer().is_some() {
         println!("=== Depth {} ===", state.current_depth);
 
-        let mut handles = browse_layer(&mut state, &conf).await?;
-        let childs = parse_layer(&mut state, &conf, &mut handles).await?;
+        let mut handles = browse_layer(&mut state, conf).await?;
+        let childs = parse_layer(&mut state, conf, &mut handles).await?;
         state.add_to_next_layer(childs);
         if state.current_depth == conf.args.depth {
             break;
@@ -41,24 +91,66 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
         println!();
     }
 
-    println!("Formatting");
-    format(&conf)?;
-
-    println!("Displaying");
-    display(&conf)?;
+    if let Some(directory) = &conf.args.download_images {
+        println!("Downloading images");
+        download::download_images(&conf.root, directory, &PERMITS).await?;
+    }
     Ok(())
 }
 
 fn format(conf: &Config) -> Result<(), Box<dyn std::error::Error>> {
-    let mut format = |node: &mut Node| {
-        Node::format(node, &conf.args.content, &conf.args.cmd).map_err(Into::into)
-    }; // Need to convert FormatError to Box< ...
-    Node::explore(&conf.root, &mut format)
+    // These formats give a single output for the whole tree, kept by the root
+    let output = match conf.args.cmd.format() {
+        Some(Format::Report) => report::render(&conf.root, &conf.args)?,
+        Some(Format::Template) => template::render(&conf.root, &conf.args)?,
+        Some(Format::Tree) => hierarchy::render(&conf.root)?,
+        Some(Format::Snapshot) => snapshot::render(&conf.root, conf.args.url())?,
+        _ => {
+            let mut format = |node: &mut Node| {
+                Node::format(node, &conf.args.content, &conf.args.cmd).map_err(Into::into)
+            }; // Need to convert FormatError to Box< ...
+            return Node::explore(&conf.root, &mut format);
+        }
+    };
+    conf.root.lock().unwrap().output = Some(output);
+    Ok(())
 }
 
-fn display(conf: &Config) -> Result<(), Box<dyn std::error::Error>> {
-    let mut display = |node: &mut Node| Node::display(node, &conf.args.cmd).map_err(Into::into);
-    Node::explore(&conf.root, &mut display)
+async fn display(conf: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    match &conf.args.cmd {
+        Display::Mirror { directory, rewrite } => {
+            Ok(mirror::mirror(&conf.root, directory, *rewrite).await?)
+        }
+        Display::Warc { name, .. } => Ok(warc::write(&conf.root, &conf.args, name)?),
+        Display::Har { name, split, .. } => Ok(har::write(&conf.root, name, *split)?),
+        Display::Graph {
+            format,
+            output,
+            no_open,
+            metric,
+        } => Ok(graph::render(
+            &conf.root.lock().unwrap(),
+            *format,
+            output.as_deref(),
+            !no_open,
+            *metric,
+        )?),
+        _ => {
+            let mut display = |node: &mut Node| Node::display(node, &conf.args).map_err(Into::into);
+            Node::explore(&conf.root, &mut display)?;
+            // Tables and markdown asked explicitly are also saved on their own, not with the default all
+            if let Display::Save { name, .. } = &conf.args.cmd {
+                let contents = &conf.args.content;
+                if contents.contains(&Content::Tables) {
+                    tables::write(&conf.root, name)?;
+                }
+                if contents.contains(&Content::Markdown) {
+                    markdown::write(&conf.root, name)?;
+                }
+            }
+            Ok(())
+        }
+    }
 }
 
 type FuturesBrowse = JoinSet<(Result<Browser, browser::BrowseError>, Arc<Mutex<Node>>)>;
@@ -69,6 +161,7 @@ async fn browse_layer(
     config: &Config,
 ) -> Result<FuturesBrowse, Box<dyn error::Error>> {
     let mut handles: FuturesBrowse = JoinSet::new();
+    let capture = config.args.capture();
     while let Some(node) = state.current_layer.pop() {
         if !config.same_domain(&node.lock().unwrap().url)
             || state.known(&node)
@@ -82,7 +175,7 @@ async fn browse_layer(
         handles.spawn(async move {
             let _permit = permit;
             let url = node.lock().unwrap().url.clone();
-            (Browser::new_navigate(&url), node)
+            (Browser::new_navigate(&url, capture), node)
         });
     }
     Ok(handles)
@@ -101,7 +194,19 @@ async fn parse_layer(
     while let Some(handle) = handles.join_next().await {
         let (browser, parent) = handle?;
         let mut explore_external = false;
-        let links = browser?.parse_document(&config.args.content, &parent).await;
+        let links = match browser {
+            Ok(browser) => browser.parse_document(&config.args, &parent).await,
+            Err(browser::BrowseError::Browser(e)) => {
+                eprintln!(
+                    "{} {}: {}",
+                    "Failed to visit".red(),
+                    parent.lock().unwrap().url,
+                    e
+                );
+                parent.lock().unwrap().error = Some(e);
+                HashSet::new()
+            }
+        };
 
         let links = links.into_iter().filter_map(|link| {
             if config.same_domain(&link) {
@@ -0,0 +1,421 @@
+use colored::Colorize;
+use markup5ever::local_name;
+use scraper::{ElementRef, Html, Node as HtmlNode, Selector};
+use serde::Serialize;
+use std::{
+    fmt, fs,
+    sync::{Arc, Mutex},
+};
+use url::Url;
+
+use crate::extract::collapse;
+use crate::node::Node;
+use crate::tables::Table;
+use crate::text;
+
+// Main content of the page converted to markdown
+#[derive(Serialize, Debug, Clone)]
+pub struct Markdown {
+    pub url: String,
+    pub title: Option<String>,
+    // RFC 3339 date of the visit of the page
+    pub crawled: String,
+    pub content: String,
+}
+
+impl Markdown {
+    // Content preceded by its front matter, the values are json strings which are valid yaml
+    pub fn document(&self) -> String {
+        let quote = |value: &str| serde_json::to_string(value).unwrap_or_default();
+        let mut document = format!("---\nurl: {}\n", quote(&self.url));
+        if let Some(title) = &self.title {
+            document.push_str(&format!("title: {}\n", quote(title)));
+        }
+        document.push_str(&format!("crawled: {}\n---\n\n", quote(&self.crawled)));
+        document.push_str(&self.content);
+        document.push('\n');
+        document
+    }
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// Emphasis around the text, the spaces it starts or ends with are left outside
+fn wrap(content: String, mark: &str) -> String {
+    let trimmed = content.trim();
+    if trimmed.is_empty() {
+        return content;
+    }
+    let start = &content[..content.len() - content.trim_start().len()];
+    let end = &content[content.trim_end().len()..];
+    format!("{start}{mark}{trimmed}{mark}{end}")
+}
+
+// Longest run of backticks in the code, the fence around it must be longer
+fn backticks(code: &str) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in code.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    longest
+}
+
+// Language given by a class like language-rust or lang-rust
+fn language<'a>(element: ElementRef<'a>) -> Option<&'a str> {
+    let code = Selector::parse("code").unwrap();
+    std::iter::once(element)
+        .chain(element.select(&code).take(1))
+        .filter_map(|element| element.value().attr("class"))
+        .flat_map(str::split_whitespace)
+        .find_map(|class| {
+            class
+                .strip_prefix("language-")
+                .or(class.strip_prefix("lang-"))
+        })
+}
+
+fn is_block(element: ElementRef) -> bool {
+    matches!(
+        element.value().name.local,
+        local_name!("address")
+            | local_name!("article")
+            | local_name!("aside")
+            | local_name!("blockquote")
+            | local_name!("dd")
+            | local_name!("details")
+            | local_name!("div")
+            | local_name!("dl")
+            | local_name!("dt")
+            | local_name!("fieldset")
+            | local_name!("figcaption")
+            | local_name!("figure")
+            | local_name!("footer")
+            | local_name!("form")
+            | local_name!("h1")
+            | local_name!("h2")
+            | local_name!("h3")
+            | local_name!("h4")
+            | local_name!("h5")
+            | local_name!("h6")
+            | local_name!("header")
+            | local_name!("hr")
+            | local_name!("li")
+            | local_name!("main")
+            | local_name!("nav")
+            | local_name!("ol")
+            | local_name!("p")
+            | local_name!("pre")
+            | local_name!("section")
+            | local_name!("summary")
+            | local_name!("table")
+            | local_name!("ul")
+    )
+}
+
+struct Converter<'a> {
+    url: &'a Url,
+}
+
+impl Converter<'_> {
+    fn absolute(&self, link: &str) -> Option<String> {
+        let link = Url::join(self.url, link.trim()).ok()?;
+        // Scripts aren't links to follow
+        (link.scheme() != "javascript").then(|| link.to_string())
+    }
+
+    // Children of the element as markdown blocks, the inline ones are gathered in paragraphs
+    fn blocks(&self, element: ElementRef) -> Vec<String> {
+        let mut blocks = Vec::new();
+        let mut paragraph = String::new();
+        for child in element.children() {
+            match child.value() {
+                HtmlNode::Text(content) => paragraph.push_str(&escape(content)),
+                HtmlNode::Element(_) => {
+                    let child = ElementRef::wrap(child).unwrap();
+                    if text::skipped(child, true) {
+                        continue;
+                    }
+                    if is_block(child) {
+                        Self::flush(&mut paragraph, &mut blocks);
+                        blocks.extend(self.block(child));
+                    } else {
+                        paragraph.push_str(&self.inline(child));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self::flush(&mut paragraph, &mut blocks);
+        blocks
+    }
+
+    // The <br> are the only line breaks kept in a paragraph
+    fn flush(paragraph: &mut String, blocks: &mut Vec<String>) {
+        let lines: Vec<String> = paragraph
+            .split('\u{0}')
+            .map(collapse)
+            .filter(|line| !line.is_empty())
+            .collect();
+        paragraph.clear();
+        if !lines.is_empty() {
+            blocks.push(lines.join("  \n"));
+        }
+    }
+
+    fn block(&self, element: ElementRef) -> Vec<String> {
+        match element.value().name.local {
+            local_name!("h1")
+            | local_name!("h2")
+            | local_name!("h3")
+            | local_name!("h4")
+            | local_name!("h5")
+            | local_name!("h6") => {
+                let level = element.value().name.local.as_bytes()[1] - b'0';
+                let title = collapse(&self.inline(element).replace('\u{0}', " "));
+                match title.is_empty() {
+                    true => Vec::new(),
+                    false => vec![format!("{} {}", "#".repeat(level as usize), title)],
+                }
+            }
+            local_name!("pre") => {
+                let code = element.text().collect::<String>();
+                let code = code.trim_matches('\n');
+                if code.trim().is_empty() {
+                    return Vec::new();
+                }
+                let fence = "`".repeat(backticks(code).max(2) + 1);
+                let language = language(element).unwrap_or_default();
+                vec![format!("{fence}{language}\n{code}\n{fence}")]
+            }
+            local_name!("ul") | local_name!("ol") => self.list(element),
+            local_name!("li") => self.blocks(element),
+            local_name!("blockquote") => {
+                let quote = self.blocks(element).join("\n\n");
+                if quote.is_empty() {
+                    return Vec::new();
+                }
+                let lines: Vec<String> = quote
+                    .lines()
+                    .map(|line| match line {
+                        "" => ">".to_owned(),
+                        line => format!("> {line}"),
+                    })
+                    .collect();
+                vec![lines.join("\n")]
+            }
+            local_name!("hr") => vec!["---".to_owned()],
+            local_name!("table") => self.table(element).into_iter().collect(),
+            _ => self.blocks(element),
+        }
+    }
+
+    // Items of the list, with the blocks after their first line indented under the marker
+    fn list(&self, element: ElementRef) -> Vec<String> {
+        let ordered = element.value().name.local == local_name!("ol");
+        let mut number: usize = element
+            .value()
+            .attr("start")
+            .and_then(|start| start.trim().parse().ok())
+            .unwrap_or(1);
+        let mut items: Vec<String> = Vec::new();
+        let children = element
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|child| !text::skipped(*child, true));
+        for child in children {
+            let item = match child.value().name.local {
+                local_name!("li") => self.blocks(child).join("\n"),
+                // Lists directly nested in a list belong to the previous item
+                local_name!("ul") | local_name!("ol") => {
+                    if let Some(last) = items.last_mut() {
+                        let nested = self.list(child).join("\n");
+                        let indent = if ordered { "   " } else { "  " };
+                        for line in nested.lines() {
+                            last.push('\n');
+                            last.push_str(indent);
+                            last.push_str(line);
+                        }
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            let marker = match ordered {
+                true => format!("{number}. "),
+                false => "- ".to_owned(),
+            };
+            number += 1;
+            let indent = " ".repeat(marker.len());
+            let mut lines = item.lines();
+            let mut entry = marker + lines.next().unwrap_or_default();
+            for line in lines {
+                entry.push('\n');
+                if !line.is_empty() {
+                    entry.push_str(&indent);
+                    entry.push_str(line);
+                }
+            }
+            items.push(entry);
+        }
+        match items.is_empty() {
+            true => Vec::new(),
+            false => vec![items.join("\n")],
+        }
+    }
+
+    // Table of github flavored markdown, the first row is the header when there is none
+    fn table(&self, element: ElementRef) -> Option<String> {
+        let table = Table::from_element(element);
+        let mut rows = table.rows.iter();
+        let headers = match table.headers.is_empty() {
+            true => rows.next()?,
+            false => &table.headers,
+        };
+        let line = |cells: &Vec<String>| {
+            let cells: Vec<String> = cells
+                .iter()
+                .map(|cell| escape(cell).replace('|', "\\|"))
+                .collect();
+            format!("| {} |", cells.join(" | "))
+        };
+        let mut lines = vec![line(headers), line(&vec!["---".to_owned(); headers.len()])];
+        lines.extend(rows.map(line));
+        Some(lines.join("\n"))
+    }
+
+    fn inline(&self, element: ElementRef) -> String {
+        let content = || {
+            let mut content = String::new();
+            for child in element.children() {
+                match child.value() {
+                    HtmlNode::Text(text) => content.push_str(&escape(text)),
+                    HtmlNode::Element(_) => {
+                        let child = ElementRef::wrap(child).unwrap();
+                        if !text::skipped(child, true) {
+                            content.push_str(&self.inline(child));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            content
+        };
+        let value = element.value();
+        match value.name.local {
+            // Marks the line breaks until the paragraph is written
+            local_name!("br") => "\u{0}".to_owned(),
+            local_name!("a") => {
+                let content = content();
+                match value.attr("href").and_then(|href| self.absolute(href)) {
+                    Some(href) if !content.trim().is_empty() => {
+                        format!("[{}]({})", collapse(&content), href.replace(')', "%29"))
+                    }
+                    _ => content,
+                }
+            }
+            local_name!("img") => match value.attr("src").and_then(|src| self.absolute(src)) {
+                Some(src) => {
+                    let alt = escape(&collapse(value.attr("alt").unwrap_or_default()));
+                    format!("![{alt}]({})", src.replace(')', "%29"))
+                }
+                None => String::new(),
+            },
+            local_name!("strong") | local_name!("b") => wrap(content(), "**"),
+            local_name!("em") | local_name!("i") => wrap(content(), "*"),
+            local_name!("del") | local_name!("s") => wrap(content(), "~~"),
+            local_name!("code") | local_name!("kbd") | local_name!("samp") => {
+                let code = collapse(&element.text().collect::<String>());
+                if code.is_empty() {
+                    return String::new();
+                }
+                let fence = "`".repeat(backticks(&code) + 1);
+                match code.starts_with('`') || code.ends_with('`') {
+                    true => format!("{fence} {code} {fence}"),
+                    false => format!("{fence}{code}{fence}"),
+                }
+            }
+            _ => content(),
+        }
+    }
+}
+
+pub fn extract_markdown(node: &Arc<Mutex<Node>>, page: &Html) {
+    // The node is only locked to store the markdown, not during the conversion
+    let url = node.lock().unwrap().url.clone();
+    let converter = Converter { url: &url };
+    let content = converter.block(text::main_content(page)).join("\n\n");
+    let title = Selector::parse("title").unwrap();
+    let title = page
+        .select(&title)
+        .next()
+        .map(|title| collapse(&title.text().collect::<String>()))
+        .filter(|title| !title.is_empty());
+    node.lock().unwrap().markdown = Some(Markdown {
+        url: url.to_string(),
+        title,
+        crawled: chrono::Utc::now().to_rfc3339(),
+        content,
+    });
+}
+
+// Write the markdown of every page in its own file, numbered by page
+pub fn write(root: &Arc<Mutex<Node>>, name: &str) -> Result<(), MarkdownError> {
+    let mut pages: Vec<Markdown> = Vec::new();
+    Node::explore(root, &mut |node: &mut Node| {
+        pages.extend(node.markdown.take());
+        Ok(())
+    })
+    .map_err(|e| MarkdownError::Message(e.to_string()))?;
+
+    for (index, markdown) in pages.iter().enumerate() {
+        let file_name = format!("{}-{}.md", name, index + 1);
+        println!("Writing {} for {}", file_name, markdown.url.green());
+        fs::write(&file_name, markdown.document())?;
+    }
+    Ok(())
+}
+
+pub enum MarkdownError {
+    IO(std::io::Error),
+    Message(String),
+}
+
+impl MarkdownError {
+    fn print(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkdownError::IO(e) => write!(f, "{}: {}", "Markdown error".red(), e),
+            MarkdownError::Message(s) => write!(f, "{}: {}", "Markdown error".red(), s),
+        }
+    }
+}
+
+impl fmt::Display for MarkdownError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl fmt::Debug for MarkdownError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl std::error::Error for MarkdownError {}
+
+impl From<std::io::Error> for MarkdownError {
+    fn from(value: std::io::Error) -> Self {
+        MarkdownError::IO(value)
+    }
+}
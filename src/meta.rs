@@ -0,0 +1,148 @@
+use scraper::{Html, Selector};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+use url::Url;
+
+use crate::extract::collapse;
+use crate::node::Node;
+
+// Metadata of the page given in its head
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Meta {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub keywords: Vec<String>,
+    pub robots: Option<String>,
+    pub canonical: Option<String>,
+    // Translations of the page, by hreflang
+    pub alternates: BTreeMap<String, String>,
+    // Properties without their og: or twitter: prefix, the first value is kept
+    pub open_graph: BTreeMap<String, String>,
+    pub twitter: BTreeMap<String, String>,
+    pub favicon: Option<String>,
+    pub lang: Option<String>,
+}
+
+// Value on a single line, or nothing if it is empty
+fn clean(value: &str) -> Option<String> {
+    Some(collapse(value)).filter(|value| !value.is_empty())
+}
+
+impl Meta {
+    fn from_page(url: &Url, page: &Html) -> Self {
+        let mut meta = Meta {
+            lang: page.root_element().attr("lang").and_then(clean),
+            ..Default::default()
+        };
+        let title = Selector::parse("title").unwrap();
+        meta.title = page
+            .select(&title)
+            .next()
+            .and_then(|title| clean(&title.text().collect::<String>()));
+
+        let tags = Selector::parse("meta[content]").unwrap();
+        for tag in page.select(&tags) {
+            let element = tag.value();
+            let Some(content) = element.attr("content").and_then(clean) else {
+                continue;
+            };
+            // OpenGraph uses property and Twitter uses name, but both are found
+            let key = element
+                .attr("property")
+                .or(element.attr("name"))
+                .unwrap_or_default()
+                .to_lowercase();
+            if let Some(property) = key.strip_prefix("og:") {
+                meta.open_graph
+                    .entry(property.to_owned())
+                    .or_insert(content);
+            } else if let Some(property) = key.strip_prefix("twitter:") {
+                meta.twitter.entry(property.to_owned()).or_insert(content);
+            } else {
+                match key.as_str() {
+                    "description" => {
+                        meta.description.get_or_insert(content);
+                    }
+                    "robots" => {
+                        meta.robots.get_or_insert(content);
+                    }
+                    "keywords" => meta.keywords.extend(content.split(',').filter_map(clean)),
+                    _ => {}
+                }
+            }
+        }
+
+        let links = Selector::parse("link[rel][href]").unwrap();
+        for link in page.select(&links) {
+            let element = link.value();
+            let Some(href) = element
+                .attr("href")
+                .and_then(|href| Url::join(url, href.trim()).ok())
+            else {
+                continue;
+            };
+            let rel = element.attr("rel").unwrap_or_default().to_lowercase();
+            let rel: Vec<&str> = rel.split_whitespace().collect();
+            if rel.contains(&"canonical") {
+                meta.canonical.get_or_insert(href.to_string());
+            } else if rel.contains(&"alternate") {
+                if let Some(lang) = element.attr("hreflang").and_then(clean) {
+                    meta.alternates.entry(lang).or_insert(href.to_string());
+                }
+            } else if rel.contains(&"icon") {
+                meta.favicon.get_or_insert(href.to_string());
+            }
+        }
+        // The language can also be given by a meta tag
+        if meta.lang.is_none() {
+            meta.lang = page
+                .select(&Selector::parse("meta[http-equiv][content]").unwrap())
+                .find(|tag| {
+                    tag.value()
+                        .attr("http-equiv")
+                        .is_some_and(|name| name.eq_ignore_ascii_case("content-language"))
+                })
+                .and_then(|tag| tag.value().attr("content").and_then(clean));
+        }
+        meta
+    }
+
+    // Every field on its own line, the properties with their prefix
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let fields = [
+            ("title", &self.title),
+            ("description", &self.description),
+            ("robots", &self.robots),
+            ("canonical", &self.canonical),
+            ("favicon", &self.favicon),
+            ("lang", &self.lang),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                lines.push(format!("{name}: {value}"));
+            }
+        }
+        if !self.keywords.is_empty() {
+            lines.push(format!("keywords: {}", self.keywords.join(", ")));
+        }
+        for (lang, url) in &self.alternates {
+            lines.push(format!("alternate {lang}: {url}"));
+        }
+        for (property, value) in &self.open_graph {
+            lines.push(format!("og:{property}: {value}"));
+        }
+        for (property, value) in &self.twitter {
+            lines.push(format!("twitter:{property}: {value}"));
+        }
+        lines
+    }
+}
+
+pub fn extract_meta(node: &Arc<Mutex<Node>>, page: &Html) {
+    let mut node = node.lock().unwrap();
+    node.meta = Some(Meta::from_page(&node.url, page));
+}
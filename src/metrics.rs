@@ -0,0 +1,155 @@
+use serde::Serialize;
+use std::collections::{BTreeSet, VecDeque};
+
+const DAMPING: f64 = 0.85;
+const ITERATIONS: usize = 100;
+
+// Importance of a page in the link graph of the crawl
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Metrics {
+    pub pagerank: f64,
+    // Number of crawled pages linking to this page, and linked by this page
+    pub in_degree: usize,
+    pub out_degree: usize,
+    // Minimum number of clicks from the seed, None if it can't be reached with links
+    pub depth: Option<usize>,
+    // Strongly connected component, the pages with the same number can all reach each other
+    pub component: usize,
+    // No link to another crawled page
+    pub dead_end: bool,
+    // No crawled page links to this one, the seed is never an orphan
+    pub orphan: bool,
+}
+
+// Metrics of the pages `0..count` linked by `edges`, the seed being the page `seed`
+pub fn compute(count: usize, edges: &[(usize, usize)], seed: usize) -> Vec<Metrics> {
+    // Several links between two pages are a single edge. Ordered so the
+    // components have the same numbers between two runs
+    let edges: BTreeSet<(usize, usize)> = edges
+        .iter()
+        .copied()
+        .filter(|(from, to)| from != to && *from < count && *to < count)
+        .collect();
+    let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); count];
+    let mut incoming: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for &(from, to) in &edges {
+        outgoing[from].push(to);
+        incoming[to].push(from);
+    }
+
+    let ranks = pagerank(&outgoing);
+    let depths = depths(&outgoing, seed);
+    let components = components(&outgoing, &incoming);
+    (0..count)
+        .map(|page| Metrics {
+            pagerank: ranks[page],
+            in_degree: incoming[page].len(),
+            out_degree: outgoing[page].len(),
+            depth: depths[page],
+            component: components[page],
+            dead_end: outgoing[page].is_empty(),
+            orphan: incoming[page].is_empty() && page != seed,
+        })
+        .collect()
+}
+
+// The rank of the pages without links is shared between every page
+fn pagerank(outgoing: &[Vec<usize>]) -> Vec<f64> {
+    let count = outgoing.len();
+    if count == 0 {
+        return Vec::new();
+    }
+    let size = count as f64;
+    let mut ranks = vec![1.0 / size; count];
+    for _ in 0..ITERATIONS {
+        let dangling = (0..count)
+            .filter(|&page| outgoing[page].is_empty())
+            .fold(0.0, |total, page| total + ranks[page]);
+        let base = (1.0 - DAMPING) / size + DAMPING * dangling / size;
+        let mut next = vec![base; count];
+        for (page, targets) in outgoing.iter().enumerate() {
+            for &target in targets {
+                next[target] += DAMPING * ranks[page] / targets.len() as f64;
+            }
+        }
+        let change = ranks
+            .iter()
+            .zip(&next)
+            .fold(0.0, |total, (old, new)| total + (old - new).abs());
+        ranks = next;
+        if change < 1e-10 {
+            break;
+        }
+    }
+    ranks
+}
+
+// Breadth first search from the seed
+fn depths(outgoing: &[Vec<usize>], seed: usize) -> Vec<Option<usize>> {
+    let mut depths = vec![None; outgoing.len()];
+    if seed >= outgoing.len() {
+        return depths;
+    }
+    depths[seed] = Some(0);
+    let mut queue = VecDeque::from([seed]);
+    while let Some(page) = queue.pop_front() {
+        let depth = depths[page].unwrap_or_default();
+        for &target in &outgoing[page] {
+            if depths[target].is_none() {
+                depths[target] = Some(depth + 1);
+                queue.push_back(target);
+            }
+        }
+    }
+    depths
+}
+
+// Kosaraju algorithm, without recursion since a crawl can be deep.
+// The components are numbered by order of discovery
+fn components(outgoing: &[Vec<usize>], incoming: &[Vec<usize>]) -> Vec<usize> {
+    let count = outgoing.len();
+
+    // Order of the pages by end of exploration
+    let mut order = Vec::with_capacity(count);
+    let mut visited = vec![false; count];
+    for start in 0..count {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut stack = vec![(start, 0)];
+        while let Some((page, next)) = stack.pop() {
+            match outgoing[page].get(next) {
+                Some(&target) => {
+                    stack.push((page, next + 1));
+                    if !visited[target] {
+                        visited[target] = true;
+                        stack.push((target, 0));
+                    }
+                }
+                None => order.push(page),
+            }
+        }
+    }
+
+    // The pages reached backward from the last one finished form a component
+    let mut components = vec![usize::MAX; count];
+    let mut component = 0;
+    for &start in order.iter().rev() {
+        if components[start] != usize::MAX {
+            continue;
+        }
+        components[start] = component;
+        let mut stack = vec![start];
+        while let Some(page) = stack.pop() {
+            for &source in &incoming[page] {
+                if components[source] == usize::MAX {
+                    components[source] = component;
+                    stack.push(source);
+                }
+            }
+        }
+        component += 1;
+    }
+    components
+}
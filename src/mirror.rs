@@ -0,0 +1,261 @@
+use colored::Colorize;
+use markup5ever::local_name;
+use scraper::Html;
+use sha1::{Digest, Sha1};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use url::Url;
+
+use crate::node::Node;
+
+// Length kept of the query in the name of a file, names are limited to 255 bytes
+const MAX_QUERY: usize = 64;
+
+// Write every explored page and the assets they reference in `directory`.
+// The tree follows the path of the urls : <host>/<path>/index.html
+pub async fn mirror(
+    root: &Arc<Mutex<Node>>,
+    directory: &Path,
+    rewrite: bool,
+) -> Result<(), MirrorError> {
+    let mut pages: Vec<(Url, String, bool)> = Vec::new();
+    let mut assets: HashSet<Url> = HashSet::new();
+    Node::explore(root, &mut |node: &mut Node| {
+        if let Some(html) = node.html.take() {
+            // Without the type of the response, the page is assumed to be html
+            let html_page = node
+                .mime
+                .as_deref()
+                .is_none_or(|mime| matches!(mime, "text/html" | "application/xhtml+xml"));
+            pages.push((without_fragment(&node.url), html, html_page));
+        }
+        assets.extend(node.assets.take().unwrap_or_default());
+        Ok(())
+    })
+    .map_err(|e| MirrorError::Message(e.to_string()))?;
+
+    // Local copy of every url, relative to the directory
+    let mut files: HashMap<Url, PathBuf> = pages
+        .iter()
+        .map(|(url, _, html_page)| (url.clone(), local_path(url, *html_page)))
+        .collect();
+
+    let client = reqwest::Client::new();
+    for asset in assets {
+        if files.contains_key(&asset) {
+            continue;
+        }
+        println!("Downloading {}", asset.as_str().green());
+        let bytes = match download(&client, &asset).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                eprintln!("{} {}: {}", "Failed to download".red(), asset, e);
+                continue;
+            }
+        };
+        let path = local_path(&asset, false);
+        if let Err(e) = write(&directory.join(&path), &bytes) {
+            eprintln!("{} {}: {}", "Failed to write".red(), asset, e);
+            continue;
+        }
+        files.insert(asset, path);
+    }
+
+    let mut written = 0;
+    for (url, html, _) in &pages {
+        let html = if rewrite {
+            rewrite_links(url, html, &files)
+        } else {
+            html.clone()
+        };
+        // A page can't be written when its path is already used by another file
+        if let Err(e) = write(&directory.join(&files[url]), html.as_bytes()) {
+            eprintln!("{} {}: {}", "Failed to write".red(), url, e);
+            continue;
+        }
+        written += 1;
+    }
+    println!(
+        "Mirrored {} pages in {}",
+        written.to_string().green(),
+        directory.display()
+    );
+    Ok(())
+}
+
+async fn download(client: &reqwest::Client, url: &Url) -> reqwest::Result<Vec<u8>> {
+    let response = client.get(url.as_str()).send().await?.error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+fn write(path: &Path, content: &[u8]) -> Result<(), MirrorError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
+fn without_fragment(url: &Url) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url
+}
+
+// Map an url to a path of the mirror. Html pages always end up in a html file so they
+// can be opened from the disk, `/docs` and `/docs/` both give `docs/index.html` and
+// `/v1.2` gives `v1.2.html`
+fn local_path(url: &Url, html: bool) -> PathBuf {
+    let host = url.host_str().unwrap_or("unknown");
+    let mut path = match url.port() {
+        Some(port) => PathBuf::from(sanitize(&format!("{host}_{port}"))),
+        None => PathBuf::from(sanitize(host)),
+    };
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+    for segment in &segments {
+        path.push(sanitize(segment));
+    }
+    if url.path().ends_with('/') || segments.is_empty() || (html && path.extension().is_none()) {
+        path.push("index.html");
+    }
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+    if html && !matches!(extension.as_deref(), Some("html" | "htm")) {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".html");
+        path.set_file_name(name);
+    }
+
+    // Different queries are different files, the hash keeps apart the queries
+    // that are the same once sanitized or truncated to keep the name short
+    if let Some(query) = url.query() {
+        let hash: String = Sha1::digest(query.as_bytes())
+            .iter()
+            .take(4)
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        let mut query = sanitize(query);
+        query.truncate(MAX_QUERY);
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match path.extension() {
+            Some(extension) => format!(
+                "{}_{}_{}.{}",
+                stem,
+                query,
+                hash,
+                extension.to_string_lossy()
+            ),
+            None => format!("{}_{}_{}", stem, query, hash),
+        };
+        path.set_file_name(name);
+    }
+    path
+}
+
+fn sanitize(segment: &str) -> String {
+    if segment == ".." || segment == "." {
+        return "_".to_owned();
+    }
+    segment
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+// Relative link from the file `from` to the file `to`, both relative to the mirror
+fn relative(from: &Path, to: &Path) -> String {
+    let from: Vec<_> = from
+        .parent()
+        .unwrap_or(Path::new(""))
+        .components()
+        .collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts = vec![".."; from.len() - common]
+        .into_iter()
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    parts.extend(
+        to[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    parts.join("/")
+}
+
+// Point every href and src that has a local copy to this copy
+fn rewrite_links(url: &Url, html: &str, files: &HashMap<Url, PathBuf>) -> String {
+    let mut document = Html::parse_document(html);
+    let from = &files[url];
+    let ids: Vec<_> = document.tree.nodes().map(|node| node.id()).collect();
+    for id in ids {
+        let Some(mut node) = document.tree.get_mut(id) else {
+            continue;
+        };
+        let scraper::Node::Element(element) = node.value() else {
+            continue;
+        };
+        for (key, value) in element.attrs.iter_mut() {
+            if !matches!(key.local, local_name!("href") | local_name!("src")) {
+                continue;
+            }
+            let Ok(target) = url.join(value) else {
+                continue;
+            };
+            if let Some(path) = files.get(&without_fragment(&target)) {
+                let mut link = relative(from, path);
+                if let Some(fragment) = target.fragment() {
+                    link = format!("{link}#{fragment}");
+                }
+                *value = link.into();
+            }
+        }
+    }
+    document.html()
+}
+
+pub enum MirrorError {
+    IO(std::io::Error),
+    Message(String),
+}
+
+impl MirrorError {
+    fn print(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MirrorError::IO(e) => write!(f, "{}: {}", "Mirror error".red(), e),
+            MirrorError::Message(s) => write!(f, "{}: {}", "Mirror error".red(), s),
+        }
+    }
+}
+
+impl fmt::Display for MirrorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl fmt::Debug for MirrorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl std::error::Error for MirrorError {}
+
+impl From<std::io::Error> for MirrorError {
+    fn from(value: std::io::Error) -> Self {
+        MirrorError::IO(value)
+    }
+}
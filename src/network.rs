@@ -0,0 +1,244 @@
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use headless_chrome::protocol::cdp::{
+    types::Event,
+    Network::{self, ResourceType},
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+// Which responses of the tab are recorded for the warc and har commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capture {
+    // Every resource loaded by the page, else only the html document of the page
+    pub assets: bool,
+    // Retrieve the body of the recorded responses, else only their headers
+    pub bodies: bool,
+}
+
+// Request and response seen by the browser while loading a page
+#[derive(Debug, Clone)]
+pub struct Exchange {
+    pub date: DateTime<Utc>,
+    // Document, Script, XHR, Fetch, ...
+    pub resource: String,
+    pub method: String,
+    pub url: String,
+    pub request_headers: Vec<(String, String)>,
+    pub post_data: Option<String>,
+    pub status: u32,
+    pub status_text: String,
+    pub protocol: String,
+    pub response_headers: Vec<(String, String)>,
+    pub mime: String,
+    pub remote_ip: Option<String>,
+    // Bytes received on the network, headers included
+    pub transfer_size: f64,
+    pub timings: Option<Timings>,
+    // Body as decoded by the browser, None if it couldn't be retrieved
+    pub body: Option<Vec<u8>>,
+}
+
+// Duration of each phase of the exchange in milliseconds, -1 if it didn't happen
+#[derive(serde::Serialize, Debug, Clone, Copy)]
+pub struct Timings {
+    pub blocked: f64,
+    pub dns: f64,
+    pub connect: f64,
+    pub ssl: f64,
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+}
+
+impl Timings {
+    fn new(timing: &Network::ResourceTiming, finished: Option<f64>) -> Self {
+        let phase = |start: f64, end: f64| if start < 0.0 { -1.0 } else { end - start };
+        let blocked = [timing.dns_start, timing.connect_start, timing.send_start]
+            .into_iter()
+            .find(|start| *start >= 0.0)
+            .unwrap_or(-1.0);
+        // Timestamps are in seconds while the timing is in milliseconds
+        let receive = finished.map_or(0.0, |finished| {
+            (finished - timing.request_time) * 1000.0 - timing.receive_headers_end
+        });
+        Timings {
+            blocked,
+            dns: phase(timing.dns_start, timing.dns_end),
+            connect: phase(timing.connect_start, timing.connect_end),
+            ssl: phase(timing.ssl_start, timing.ssl_end),
+            send: timing.send_end - timing.send_start,
+            wait: timing.receive_headers_end - timing.send_end,
+            receive: receive.max(0.0),
+        }
+    }
+
+    // Time of the whole exchange, ssl is already part of connect
+    pub fn total(&self) -> f64 {
+        [
+            self.blocked,
+            self.dns,
+            self.connect,
+            self.send,
+            self.wait,
+            self.receive,
+        ]
+        .into_iter()
+        .filter(|time| *time > 0.0)
+        .fold(0.0, |total, time| total + time)
+    }
+}
+
+pub type Exchanges = Arc<Mutex<Vec<Exchange>>>;
+
+// Status and type of the response of the page
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u32,
+    pub mime: String,
+}
+
+// Keep only the response of the page, to know its status without recording the
+// traffic. Must be called before navigating
+pub fn record_page(
+    tab: &headless_chrome::Tab,
+    page: &Arc<Mutex<Option<Response>>>,
+) -> anyhow::Result<()> {
+    let page = Arc::clone(page);
+    tab.register_response_handling(
+        "coma-page",
+        Box::new(move |params, _| {
+            if params.Type != ResourceType::Document {
+                return;
+            }
+            // The frames of the page are documents loaded after it
+            page.lock().unwrap().get_or_insert(Response {
+                status: params.response.status,
+                mime: params.response.mime_type,
+            });
+        }),
+    )?;
+    Ok(())
+}
+
+type Requests = Arc<Mutex<HashMap<String, Network::events::RequestWillBeSentEventParams>>>;
+
+// Record the network traffic of the tab in `exchanges`.
+// Must be called before navigating
+pub fn record(
+    tab: &headless_chrome::Tab,
+    capture: Capture,
+    exchanges: &Exchanges,
+) -> anyhow::Result<()> {
+    // The response doesn't contain the request sent, keep them until the response comes.
+    // Same for the end of the loading, the listeners are called before the response handler
+    let requests: Requests = Arc::default();
+    let finished: Arc<Mutex<HashMap<String, (f64, f64)>>> = Arc::default();
+
+    let (pending, loaded) = (Arc::clone(&requests), Arc::clone(&finished));
+    tab.add_event_listener(Arc::new(move |event: &Event| match event {
+        Event::NetworkRequestWillBeSent(event) => {
+            pending
+                .lock()
+                .unwrap()
+                .insert(event.params.request_id.clone(), event.params.clone());
+        }
+        Event::NetworkLoadingFinished(event) => {
+            loaded.lock().unwrap().insert(
+                event.params.request_id.clone(),
+                (event.params.timestamp, event.params.encoded_data_length),
+            );
+        }
+        _ => {}
+    }))?;
+
+    let exchanges = Arc::clone(exchanges);
+    tab.register_response_handling(
+        "coma",
+        Box::new(move |params, fetch_body| {
+            if !capture.assets && params.Type != ResourceType::Document {
+                return;
+            }
+            let request = requests.lock().unwrap().remove(&params.request_id);
+            let (finished, transfer_size) =
+                match finished.lock().unwrap().remove(&params.request_id) {
+                    Some((timestamp, size)) => (Some(timestamp), size),
+                    None => (None, params.response.encoded_data_length),
+                };
+            let body = if capture.bodies {
+                fetch_body().ok().and_then(|body| {
+                    if body.base_64_encoded {
+                        base64::engine::general_purpose::STANDARD
+                            .decode(body.body)
+                            .ok()
+                    } else {
+                        Some(body.body.into_bytes())
+                    }
+                })
+            } else {
+                None
+            };
+            let response = params.response;
+            let date = request
+                .as_ref()
+                .and_then(|request| {
+                    DateTime::from_timestamp_millis((request.wall_time * 1000.0) as i64)
+                })
+                .unwrap_or_else(Utc::now);
+            let (method, request_headers, post_data) = match request {
+                Some(request) => (
+                    request.request.method,
+                    headers(&request.request.headers),
+                    request.request.post_data,
+                ),
+                None => (
+                    "GET".to_owned(),
+                    response
+                        .request_headers
+                        .as_ref()
+                        .map(headers)
+                        .unwrap_or_default(),
+                    None,
+                ),
+            };
+            exchanges.lock().unwrap().push(Exchange {
+                date,
+                resource: format!("{:?}", params.Type),
+                method,
+                url: response.url,
+                request_headers,
+                post_data,
+                status: response.status,
+                status_text: response.status_text,
+                protocol: response.protocol.unwrap_or_else(|| "http/1.1".to_owned()),
+                response_headers: headers(&response.headers),
+                mime: response.mime_type,
+                remote_ip: response.remote_ip_address,
+                transfer_size,
+                timings: response
+                    .timing
+                    .as_ref()
+                    .map(|timing| Timings::new(timing, finished)),
+                body,
+            });
+        }),
+    )?;
+    Ok(())
+}
+
+fn headers(headers: &Network::Headers) -> Vec<(String, String)> {
+    let Some(serde_json::Value::Object(map)) = &headers.0 else {
+        return Vec::new();
+    };
+    map.iter()
+        .map(|(name, value)| {
+            let value = match value {
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            (name.clone(), value)
+        })
+        .collect()
+}
@@ -1,24 +1,66 @@
-use std::sync::{Arc, Mutex, Weak};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, Weak},
+};
 use url::Url;
 
+use crate::download::ImageFile;
+use crate::extract::Link;
+use crate::forms::Form;
+use crate::markdown::Markdown;
+use crate::meta::Meta;
+use crate::network::Exchange;
+use crate::patterns::Match;
+use crate::rules::Record;
+use crate::structured::Structured;
+use crate::tables::Table;
+
 pub struct Node {
     pub id: String,
     pub url: Url,
     // TODO : should I move while Node behind of mutex instead of most field ?
     // TODO : I could use a color to show difference between explored and unexplored node
     pub explored: bool, // flag used to know if it will be rendered
+    // Reason why the page couldn't be visited
+    pub error: Option<String>,
+    // Status code of the response of the page
+    pub status: Option<u32>,
+    // Type of the response of the page, like text/html
+    pub mime: Option<String>,
     // Every node will own every images on the page
     // More logic that every node own a copy of the url to the image
 
     // Mutex is need to borrow mutability of Arc
     pub images: Option<Vec<Url>>,
+    // Local copies of the images when they are downloaded
+    pub image_files: Option<Vec<ImageFile>>,
     // Can't directly use scraper::node::{Comment, Text} since their aren't Send/Sync
     // Could try later to impl these trait
     pub comments: Option<Vec<String>>,
     pub texts: Option<Vec<String>>,
+    pub visible: Option<Vec<String>>,
     pub inputs: Option<Vec<String>>,
+    pub forms: Option<Vec<Form>>,
     pub links: Option<Vec<Url>>,
+    pub meta: Option<Meta>,
+    pub structured: Option<Structured>,
+    pub tables: Option<Vec<Table>>,
+    pub markdown: Option<Markdown>,
+    // Values found by the selectors given by the user, by name
+    pub extracted: BTreeMap<String, Vec<String>>,
+    // Matches of the regexes given by the user, with their context
+    pub matches: Vec<Match>,
+    // Records of the rules given by the user applying to the page
+    pub records: Vec<Record>,
+    // Every anchor of the page, always kept to build the graph
+    pub anchors: Vec<Link>,
+    // Rendered html and the assets it references, only kept to mirror the site
+    pub html: Option<String>,
+    pub assets: Option<Vec<Url>>,
+    // Network traffic recorded while loading the page
+    pub exchanges: Option<Vec<Exchange>>,
     pub children: Vec<Arc<Mutex<Node>>>,
+    #[allow(dead_code)] // not used yet, but keep the link to the parents
     pub parents: Vec<Weak<Mutex<Node>>>,
 
     // Output already formatted as wanted
@@ -46,11 +88,28 @@ impl Node {
             id,
             url,
             explored: false,
+            error: None,
+            status: None,
+            mime: None,
             images: None,
+            image_files: None,
             comments: None,
             texts: None,
+            visible: None,
             inputs: None,
+            forms: None,
             links: None,
+            meta: None,
+            structured: None,
+            tables: None,
+            markdown: None,
+            extracted: BTreeMap::new(),
+            matches: Vec::new(),
+            records: Vec::new(),
+            anchors: Vec::new(),
+            html: None,
+            assets: None,
+            exchanges: None,
             children: vec![],
             parents: parent.map_or_else(Vec::new, |p| vec![Arc::downgrade(p)]),
             output: None,
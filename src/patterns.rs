@@ -0,0 +1,205 @@
+use markup5ever::local_name;
+use regex::Regex;
+use scraper::Html;
+use serde::Serialize;
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use crate::cli::RegexSource;
+use crate::node::Node;
+
+// Characters kept on each side of a match
+const CONTEXT: usize = 40;
+
+// Common patterns, with the part of the page they are searched in when it is not the
+// one asked with --regex-in
+const PRESETS: [(&str, &str, Option<RegexSource>); 7] = [
+    (
+        "emails",
+        r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+        None,
+    ),
+    (
+        "phones",
+        r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{1,4}\)[ .-]?|\d{1,4}[ .-])\d{2,4}(?:[ .-]\d{2,4}){1,4}\b",
+        None,
+    ),
+    (
+        "ipv4",
+        r"\b(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\b",
+        None,
+    ),
+    (
+        "uuids",
+        r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b",
+        None,
+    ),
+    ("aws-keys", r"\b(?:AKIA|ASIA|ABIA|ACCA)[0-9A-Z]{16}\b", None),
+    (
+        "jwt",
+        r"\beyJ[A-Za-z0-9_-]+\.eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*",
+        None,
+    ),
+    // Quoted absolute urls and paths
+    (
+        "urls-in-js",
+        r#"["'`]((?:https?:)?//[^"'`\s<>]+|/[\w\-.~%/]+(?:\?[^"'`\s<>]*)?)["'`]"#,
+        Some(RegexSource::Scripts),
+    ),
+];
+
+// Named regex, written `name=pattern` or the name of a preset
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pub name: String,
+    pub regex: Regex,
+    // Part of the page always searched, instead of the ones given with --regex-in
+    pub source: Option<RegexSource>,
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, pattern, source) = match s.split_once('=') {
+            Some((name, pattern)) if !name.trim().is_empty() => (name.trim(), pattern, None),
+            Some(_) => return Err(format!("{s}: expected name=pattern or a preset")),
+            None => PRESETS
+                .iter()
+                .find(|(name, _, _)| *name == s)
+                .map(|(name, pattern, source)| (*name, *pattern, *source))
+                .ok_or_else(|| {
+                    let presets: Vec<&str> = PRESETS.iter().map(|(name, _, _)| *name).collect();
+                    format!(
+                        "{s}: expected name=pattern or a preset among {}",
+                        presets.join(", ")
+                    )
+                })?,
+        };
+        Ok(Pattern {
+            name: name.to_owned(),
+            regex: Regex::new(pattern).map_err(|e| e.to_string())?,
+            source,
+        })
+    }
+}
+
+// Match of a regex with the text around it
+#[derive(Serialize, Debug, Clone)]
+pub struct Match {
+    pub name: String,
+    pub url: String,
+    pub source: RegexSource,
+    pub value: String,
+    pub context: String,
+}
+
+// Text of the page outside of the scripts and styles
+fn texts(page: &Html) -> String {
+    page.tree
+        .nodes()
+        .filter_map(|node| {
+            let text = node.value().as_text()?;
+            let parent = node.parent()?;
+            match parent.value().as_element()?.name.local {
+                local_name!("script") | local_name!("style") | local_name!("noscript") => None,
+                _ => Some(&**text),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn scripts(page: &Html) -> String {
+    page.tree
+        .nodes()
+        .filter_map(|node| {
+            let text = node.value().as_text()?;
+            let parent = node.parent()?;
+            (parent.value().as_element()?.name.local == local_name!("script")).then_some(&**text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn comments(page: &Html) -> String {
+    page.tree
+        .values()
+        .filter_map(|node| node.as_comment().map(|comment| &**comment))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Part of the page searched by the regexes
+pub fn haystack(page: &Html, html: &str, source: RegexSource) -> String {
+    match source {
+        RegexSource::Texts => texts(page),
+        RegexSource::Html => html.to_owned(),
+        RegexSource::Comments => comments(page),
+        RegexSource::Scripts => scripts(page),
+    }
+}
+
+// Text around the match on a single line
+fn context(haystack: &str, start: usize, end: usize) -> String {
+    let mut before = start.saturating_sub(CONTEXT);
+    while !haystack.is_char_boundary(before) {
+        before -= 1;
+    }
+    let mut after = (end + CONTEXT).min(haystack.len());
+    while !haystack.is_char_boundary(after) {
+        after += 1;
+    }
+    haystack[before..after]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Matches of every regex in the parts of the page it is searched in. The value is the
+// first group of the regex when it has one, the whole match otherwise
+pub fn extract_matches(
+    node: &Arc<Mutex<Node>>,
+    page: &Html,
+    html: &str,
+    patterns: &[Pattern],
+    sources: &[RegexSource],
+) {
+    if patterns.is_empty() {
+        return;
+    }
+    let haystacks = [
+        RegexSource::Texts,
+        RegexSource::Html,
+        RegexSource::Comments,
+        RegexSource::Scripts,
+    ]
+    .map(|source| (source, haystack(page, html, source)));
+    let mut node = node.lock().unwrap();
+    let url = node.url.to_string();
+    for pattern in patterns {
+        for (source, haystack) in &haystacks {
+            let searched = match pattern.source {
+                Some(only) => only == *source,
+                None => sources.contains(source),
+            };
+            if !searched {
+                continue;
+            }
+            for captures in pattern.regex.captures_iter(haystack) {
+                let Some(found) = captures.get(1).or_else(|| captures.get(0)) else {
+                    continue;
+                };
+                node.matches.push(Match {
+                    name: pattern.name.clone(),
+                    url: url.clone(),
+                    source: *source,
+                    value: found.as_str().to_owned(),
+                    context: context(haystack, found.start(), found.end()),
+                });
+            }
+        }
+    }
+}
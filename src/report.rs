@@ -0,0 +1,150 @@
+use std::{
+    error,
+    fmt::Write,
+    sync::{Arc, Mutex},
+};
+
+use crate::cli::Cli;
+use crate::extract::collapse;
+use crate::node::Node;
+
+// Data of a page kept for the report
+struct Page {
+    url: String,
+    error: Option<String>,
+    texts: usize,
+    links: Vec<String>,
+    images: Vec<String>,
+    comments: Vec<String>,
+    inputs: Vec<String>,
+}
+
+impl Page {
+    fn from_node(node: &Node) -> Self {
+        Page {
+            url: node.url.to_string(),
+            error: node.error.clone(),
+            texts: node.texts.as_ref().map_or(0, Vec::len),
+            links: node
+                .links
+                .iter()
+                .flatten()
+                .map(|url| url.to_string())
+                .collect(),
+            images: node
+                .images
+                .iter()
+                .flatten()
+                .map(|url| url.to_string())
+                .collect(),
+            comments: node.comments.clone().unwrap_or_default(),
+            inputs: node.inputs.clone().unwrap_or_default(),
+        }
+    }
+}
+
+// Markdown document describing the whole crawl, for people that won't read json
+pub fn render(root: &Arc<Mutex<Node>>, args: &Cli) -> Result<String, Box<dyn error::Error>> {
+    let mut pages: Vec<Page> = Vec::new();
+    Node::explore(root, &mut |node: &mut Node| {
+        pages.push(Page::from_node(node));
+        Ok(())
+    })?;
+
+    let mut report = String::new();
+    writeln!(report, "# Crawl report of {}\n", args.url())?;
+    writeln!(
+        report,
+        "Generated by coma {} on {}, with a depth of {}.\n",
+        env!("CARGO_PKG_VERSION"),
+        chrono::Utc::now().format("%Y-%m-%d %H:%M UTC"),
+        args.depth
+    )?;
+
+    let errors = pages.iter().filter(|page| page.error.is_some()).count();
+    let count = |f: fn(&Page) -> usize| pages.iter().map(f).sum::<usize>();
+    writeln!(report, "## Summary\n")?;
+    writeln!(report, "| | Count |\n|---|---:|")?;
+    writeln!(report, "| Pages crawled | {} |", pages.len())?;
+    writeln!(report, "| Errors | {} |", errors)?;
+    writeln!(report, "| Texts | {} |", count(|page| page.texts))?;
+    writeln!(report, "| Links | {} |", count(|page| page.links.len()))?;
+    writeln!(report, "| Images | {} |", count(|page| page.images.len()))?;
+    writeln!(
+        report,
+        "| Comments | {} |",
+        count(|page| page.comments.len())
+    )?;
+    writeln!(report, "| Inputs | {} |", count(|page| page.inputs.len()))?;
+
+    writeln!(report, "\n## Pages\n")?;
+    writeln!(
+        report,
+        "| Page | Links | Images | Comments | Inputs | Status |\n|---|---:|---:|---:|---:|---|"
+    )?;
+    for page in &pages {
+        writeln!(
+            report,
+            "| <{}> | {} | {} | {} | {} | {} |",
+            page.url,
+            page.links.len(),
+            page.images.len(),
+            page.comments.len(),
+            page.inputs.len(),
+            page.error
+                .as_ref()
+                .map_or("ok".to_owned(), |e| format!("error: {}", cell(e)))
+        )?;
+    }
+
+    for page in &pages {
+        writeln!(report, "\n## {}", page.url)?;
+        if let Some(e) = &page.error {
+            writeln!(report, "\n> **Error:** {}", collapse(e))?;
+            continue;
+        }
+        section(&mut report, "Links", &page.links, |link| {
+            format!("<{link}>")
+        })?;
+        section(&mut report, "Images", &page.images, |image| {
+            format!("<{image}>")
+        })?;
+        section(&mut report, "Comments", &page.comments, |comment| {
+            code(comment)
+        })?;
+        section(&mut report, "Form inputs", &page.inputs, |input| {
+            code(input)
+        })?;
+    }
+    Ok(report)
+}
+
+fn section(
+    report: &mut String,
+    title: &str,
+    items: &[String],
+    item: impl Fn(&str) -> String,
+) -> std::fmt::Result {
+    if items.is_empty() {
+        return Ok(());
+    }
+    writeln!(report, "\n### {title}\n")?;
+    for value in items {
+        writeln!(report, "- {}", item(value))?;
+    }
+    Ok(())
+}
+
+fn cell(text: &str) -> String {
+    collapse(text).replace('|', "\\|")
+}
+
+// Code span that can contain backticks
+fn code(text: &str) -> String {
+    let text = collapse(text);
+    if text.contains('`') {
+        format!("`` {text} ``")
+    } else {
+        format!("`{text}`")
+    }
+}
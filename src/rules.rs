@@ -0,0 +1,325 @@
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    sync::{Arc, LazyLock, Mutex},
+};
+use url::Url;
+
+use crate::cli::RegexSource;
+use crate::extract::{self, Target};
+use crate::node::Node;
+use crate::patterns;
+
+// File given with --rules, every record is extracted from the pages it applies to
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    records: BTreeMap<String, RecordFile>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct RecordFile {
+    // Regexes matched on the url of the page, every page when empty
+    #[serde(default)]
+    urls: Vec<String>,
+    fields: BTreeMap<String, FieldFile>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct FieldFile {
+    css: Option<String>,
+    xpath: Option<String>,
+    regex: Option<String>,
+    // Attribute taken by the css selector, or html for the inner html
+    attr: Option<String>,
+    // Part of the page searched by the regex
+    source: Option<RegexSource>,
+    #[serde(default)]
+    multiple: bool,
+    #[serde(default)]
+    process: Vec<Process>,
+}
+
+// Transformation applied to every value, in the given order
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Process {
+    Trim,
+    ToNumber,
+    AbsoluteUrl,
+}
+
+#[derive(Debug, Clone)]
+enum Extractor {
+    Css(Selector, Target),
+    XPath(String),
+    Regex(Regex, RegexSource),
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    name: String,
+    extractor: Extractor,
+    multiple: bool,
+    process: Vec<Process>,
+}
+
+#[derive(Debug, Clone)]
+struct RecordRule {
+    name: String,
+    urls: Vec<Regex>,
+    fields: Vec<Field>,
+}
+
+// Rules checked when the arguments are parsed
+#[derive(Debug, Clone)]
+pub struct Rules {
+    records: Vec<RecordRule>,
+}
+
+// Fields extracted from a page by a record rule
+#[derive(Serialize, Debug, Clone)]
+pub struct Record {
+    pub name: String,
+    pub url: String,
+    pub fields: BTreeMap<String, serde_json::Value>,
+    // Fields that couldn't be extracted from the page, they are null
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+impl Field {
+    fn new(name: &str, file: FieldFile) -> Result<Self, String> {
+        let extractor = match (file.css, file.xpath, file.regex) {
+            (Some(css), None, None) => Extractor::Css(
+                Selector::parse(&css).map_err(|e| format!("{css}: {e}"))?,
+                file.attr.as_deref().map_or(Target::Text, Target::from_attr),
+            ),
+            (None, Some(xpath), None) => {
+                extract::check_xpath(&xpath)?;
+                Extractor::XPath(xpath)
+            }
+            (None, None, Some(regex)) => Extractor::Regex(
+                Regex::new(&regex).map_err(|e| e.to_string())?,
+                file.source.unwrap_or(RegexSource::Texts),
+            ),
+            _ => return Err("expected one of css, xpath or regex".to_owned()),
+        };
+        if file.attr.is_some() && !matches!(extractor, Extractor::Css(..)) {
+            return Err("attr is only used with css".to_owned());
+        }
+        if file.source.is_some() && !matches!(extractor, Extractor::Regex(..)) {
+            return Err("source is only used with regex".to_owned());
+        }
+        Ok(Field {
+            name: name.to_owned(),
+            extractor,
+            multiple: file.multiple,
+            process: file.process,
+        })
+    }
+}
+
+impl Rules {
+    // Read and check the rules, used as the parser of --rules
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("can't read {path}: {e}"))?;
+        let file: RulesFile = toml::from_str(&content).map_err(|e| format!("{path}: {e}"))?;
+        let mut records = Vec::new();
+        for (name, record) in file.records {
+            let urls = record
+                .urls
+                .iter()
+                .map(|url| Regex::new(url).map_err(|e| format!("{path}: record {name}: {e}")))
+                .collect::<Result<_, _>>()?;
+            let fields = record
+                .fields
+                .into_iter()
+                .map(|(field, file)| {
+                    Field::new(&field, file)
+                        .map_err(|e| format!("{path}: field {name}.{field}: {e}"))
+                })
+                .collect::<Result<_, _>>()?;
+            records.push(RecordRule { name, urls, fields });
+        }
+        Ok(Rules { records })
+    }
+}
+
+// First number of the value, grouped by spaces or apostrophes like 1 234,50 € or
+// by dots and commas like $1,234.50. A minus stuck to a word, like in SKU-12, is a dash
+static NUMBER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?:(?:^|[^\p{L}\p{N}])(-))?(\d{1,3}(?:[ \u{a0}\u{202f}']\d{3}\b)+(?:[.,]\d+)?|\d+(?:[.,]\d+)*)",
+    )
+    .unwrap()
+});
+
+// Groups of thousands after the first one have 3 digits
+fn thousands(groups: &[&str]) -> bool {
+    groups
+        .first()
+        .is_some_and(|first| (1..=3).contains(&first.len()))
+        && groups.iter().skip(1).all(|group| group.len() == 3)
+}
+
+// Integer and decimal parts of the number, nothing when the separators can't be told
+// apart like in 1.234 which is 1234 in Europe and 1.234 elsewhere
+fn parts(number: &str, grouped: bool) -> Option<(String, String)> {
+    let separators: Vec<char> = number.chars().filter(|c| matches!(c, '.' | ',')).collect();
+    let Some(&last) = separators.last() else {
+        return Some((number.to_owned(), String::new()));
+    };
+    let (integer, decimals) = number.rsplit_once(last)?;
+    let other = if last == '.' { ',' } else { '.' };
+    let count = separators.iter().filter(|c| **c == last).count();
+    match (grouped, separators.contains(&other), count) {
+        // The thousands are already grouped, the separator is the decimal one
+        (true, _, 1) if separators.len() == 1 => Some((integer.to_owned(), decimals.to_owned())),
+        (true, _, _) => None,
+        // The last separator is the decimal one, the other groups the thousands
+        (false, true, 1) => {
+            let groups: Vec<&str> = integer.split(other).collect();
+            thousands(&groups).then(|| (groups.concat(), decimals.to_owned()))
+        }
+        (false, true, _) => None,
+        // The same separator several times groups the thousands
+        (false, false, 1) => {
+            let ambiguous = decimals.len() == 3 && integer.len() <= 3 && integer != "0";
+            (!ambiguous).then(|| (integer.to_owned(), decimals.to_owned()))
+        }
+        (false, false, _) => {
+            let groups: Vec<&str> = number.split(last).collect();
+            thousands(&groups).then(|| (groups.concat(), String::new()))
+        }
+    }
+}
+
+fn to_number(value: &str) -> Option<f64> {
+    let captures = NUMBER.captures(value)?;
+    let token = &captures[2];
+    let grouped = token.contains(|c: char| !c.is_ascii_digit() && !matches!(c, '.' | ','));
+    let number: String = token
+        .chars()
+        .filter(|c| c.is_ascii_digit() || matches!(c, '.' | ','))
+        .collect();
+    let (integer, decimals) = parts(&number, grouped)?;
+    let sign = captures.get(1).map_or("", |minus| minus.as_str());
+    let number = match decimals.is_empty() {
+        true => format!("{sign}{integer}"),
+        false => format!("{sign}{integer}.{decimals}"),
+    };
+    number.parse().ok()
+}
+
+fn process(value: String, steps: &[Process], url: &Url) -> serde_json::Value {
+    let mut value = serde_json::Value::String(value);
+    for step in steps {
+        let serde_json::Value::String(text) = &value else {
+            break;
+        };
+        value = match step {
+            Process::Trim => text.split_whitespace().collect::<Vec<_>>().join(" ").into(),
+            Process::ToNumber => to_number(text).map_or(serde_json::Value::Null, Into::into),
+            Process::AbsoluteUrl => Url::join(url, text.trim())
+                .map_or(serde_json::Value::Null, |url| url.to_string().into()),
+        };
+    }
+    value
+}
+
+// Records of the rules applying to the page. A field is null, or an empty list when
+// it is multiple, if nothing is found
+pub fn extract_records(node: &Arc<Mutex<Node>>, page: &Html, html: &str, rules: &Rules) {
+    let url = node.lock().unwrap().url.clone();
+    // The copy queried with XPath is only made when needed
+    let package = rules
+        .records
+        .iter()
+        .flat_map(|rule| &rule.fields)
+        .any(|field| matches!(field.extractor, Extractor::XPath(_)))
+        .then(|| extract::xml_document(page));
+    let document = package.as_ref().map(|package| package.as_document());
+    let mut records = Vec::new();
+    for rule in &rules.records {
+        if !rule.urls.is_empty() && !rule.urls.iter().any(|regex| regex.is_match(url.as_str())) {
+            continue;
+        }
+        let mut fields = BTreeMap::new();
+        let mut errors = Vec::new();
+        for field in &rule.fields {
+            let values = match &field.extractor {
+                Extractor::Css(selector, target) => extract::select(page, selector, target),
+                Extractor::XPath(expr) => match document
+                    .as_ref()
+                    .map(|document| extract::evaluate(document, expr))
+                {
+                    Some(Ok(values)) => values,
+                    None => Vec::new(),
+                    Some(Err(e)) => {
+                        errors.push(format!("{}: {}", field.name, e));
+                        Vec::new()
+                    }
+                },
+                Extractor::Regex(regex, source) => {
+                    let haystack = patterns::haystack(page, html, *source);
+                    regex
+                        .captures_iter(&haystack)
+                        .filter_map(|captures| captures.get(1).or_else(|| captures.get(0)))
+                        .map(|found| found.as_str().to_owned())
+                        .collect()
+                }
+            };
+            let mut values = values
+                .into_iter()
+                .map(|value| process(value, &field.process, &url));
+            let value = if field.multiple {
+                serde_json::Value::Array(values.collect())
+            } else {
+                values.next().unwrap_or_default()
+            };
+            fields.insert(field.name.clone(), value);
+        }
+        records.push(Record {
+            name: rule.name.clone(),
+            url: url.to_string(),
+            fields,
+            errors,
+        });
+    }
+    node.lock().unwrap().records = records;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_number;
+
+    #[test]
+    fn grouped_thousands() {
+        assert_eq!(to_number("1 234,50 €"), Some(1234.5));
+        assert_eq!(to_number("$1,234.50"), Some(1234.5));
+        assert_eq!(to_number("1.000.000,5"), Some(1000000.5));
+    }
+
+    #[test]
+    fn ambiguous_separator() {
+        assert_eq!(to_number("1.234"), None);
+    }
+
+    #[test]
+    fn decimals() {
+        assert_eq!(to_number("0.125"), Some(0.125));
+    }
+
+    #[test]
+    fn sign() {
+        assert_eq!(to_number("SKU-12"), Some(12.0));
+        assert_eq!(to_number(" -5"), Some(-5.0));
+    }
+}
@@ -0,0 +1,224 @@
+use askama::Template;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, net,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crate::graph;
+use crate::node::Node;
+use crate::template::Page;
+
+#[derive(Template)]
+#[template(path = "serve.html")]
+struct ServeTemplate {
+    // Renderer bundled in the binary so the page works offline
+    script: &'static str,
+}
+
+// Data shared by the requests, the tree is filled by the crawl while it is served
+#[derive(Clone)]
+struct Shared {
+    root: Arc<Mutex<Node>>,
+    crawling: Arc<AtomicBool>,
+}
+
+pub struct Server {
+    url: String,
+    crawling: Arc<AtomicBool>,
+}
+
+impl Server {
+    // The port is bound before the crawl to fail early. The server runs on its own
+    // thread since the browsers block the threads of the main runtime while crawling
+    pub fn start(root: &Arc<Mutex<Node>>, address: &str, port: u16) -> Result<Self, ServeError> {
+        let listener = net::TcpListener::bind((address, port))?;
+        listener.set_nonblocking(true)?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let shared = Shared {
+            root: Arc::clone(root),
+            crawling: Arc::new(AtomicBool::new(true)),
+        };
+        let crawling = Arc::clone(&shared.crawling);
+
+        let app = Router::new()
+            .route("/", get(index))
+            .route("/api/status", get(status))
+            .route("/api/pages", get(pages))
+            .route("/api/page", get(page))
+            .route("/api/graph", get(graph))
+            .with_state(shared);
+        let runtime = tokio::runtime::Runtime::new()?;
+        thread::spawn(move || {
+            runtime.block_on(async {
+                let listener = match tokio::net::TcpListener::from_std(listener) {
+                    Ok(listener) => listener,
+                    Err(e) => return eprintln!("{}", ServeError::IO(e)),
+                };
+                if let Err(e) = axum::serve(listener, app).await {
+                    eprintln!("{}", ServeError::IO(e));
+                }
+            })
+        });
+        println!("Serving on {}", url.green());
+        Ok(Server { url, crawling })
+    }
+
+    // Keep serving the results once the crawl is over, until ctrl-c
+    pub async fn finished(self) -> Result<(), ServeError> {
+        self.crawling.store(false, Ordering::Relaxed);
+        println!(
+            "Crawl finished, results served on {}. Press ctrl-c to stop",
+            self.url.green()
+        );
+        tokio::signal::ctrl_c().await?;
+        Ok(())
+    }
+}
+
+async fn index() -> Response {
+    let template = ServeTemplate {
+        script: include_str!("../static/graph.js"),
+    };
+    match template.render() {
+        Ok(html) => Html(html).into_response(),
+        Err(e) => error(e.to_string()),
+    }
+}
+
+#[derive(Serialize)]
+struct Status {
+    crawling: bool,
+    pages: usize,
+}
+
+async fn status(State(shared): State<Shared>) -> Response {
+    let mut pages = 0;
+    let explored = Node::explore(&shared.root, &mut |_: &mut Node| {
+        pages += 1;
+        Ok(())
+    });
+    match explored {
+        Ok(()) => Json(Status {
+            crawling: shared.crawling.load(Ordering::Relaxed),
+            pages,
+        })
+        .into_response(),
+        Err(e) => error(e.to_string()),
+    }
+}
+
+// Page of the list, only with the amount of each content
+#[derive(Serialize)]
+struct Summary {
+    id: String,
+    url: String,
+    error: Option<String>,
+    texts: usize,
+    comments: usize,
+    links: usize,
+    images: usize,
+    inputs: usize,
+}
+
+impl Summary {
+    fn from_node(node: &Node) -> Self {
+        Summary {
+            id: node.id.clone(),
+            url: node.url.to_string(),
+            error: node.error.clone(),
+            texts: node.texts.as_ref().map_or(0, Vec::len),
+            comments: node.comments.as_ref().map_or(0, Vec::len),
+            links: node.links.as_ref().map_or(0, Vec::len),
+            images: node.images.as_ref().map_or(0, Vec::len),
+            inputs: node.inputs.as_ref().map_or(0, Vec::len),
+        }
+    }
+}
+
+async fn pages(State(shared): State<Shared>) -> Response {
+    let mut pages: Vec<Summary> = Vec::new();
+    let explored = Node::explore(&shared.root, &mut |node: &mut Node| {
+        pages.push(Summary::from_node(node));
+        Ok(())
+    });
+    match explored {
+        Ok(()) => Json(pages).into_response(),
+        Err(e) => error(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct PageQuery {
+    id: String,
+}
+
+async fn page(State(shared): State<Shared>, Query(query): Query<PageQuery>) -> Response {
+    let mut found: Option<Page> = None;
+    let explored = Node::explore(&shared.root, &mut |node: &mut Node| {
+        if found.is_none() && node.id == query.id {
+            found = Some(Page::from_node(node));
+        }
+        Ok(())
+    });
+    match (explored, found) {
+        (Err(e), _) => error(e.to_string()),
+        (Ok(()), Some(page)) => Json(page).into_response(),
+        (Ok(()), None) => (StatusCode::NOT_FOUND, "page not found").into_response(),
+    }
+}
+
+async fn graph(State(shared): State<Shared>) -> Response {
+    match graph::json(&shared.root.lock().unwrap()) {
+        Ok(graph) => Json(graph).into_response(),
+        Err(e) => error(e.to_string()),
+    }
+}
+
+fn error(message: String) -> Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, message).into_response()
+}
+
+pub enum ServeError {
+    IO(std::io::Error),
+}
+
+impl ServeError {
+    fn print(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServeError::IO(e) => write!(f, "{}: {}", "Serve error".red(), e),
+        }
+    }
+}
+
+impl fmt::Display for ServeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl fmt::Debug for ServeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl std::error::Error for ServeError {}
+
+impl From<std::io::Error> for ServeError {
+    fn from(value: std::io::Error) -> Self {
+        ServeError::IO(value)
+    }
+}
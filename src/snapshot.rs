@@ -0,0 +1,116 @@
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    error, fmt, fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::node::Node;
+
+// Whole crawl saved to be compared with a later one
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Snapshot {
+    pub seed: String,
+    // RFC 3339 date of the crawl
+    pub date: String,
+    pub pages: Vec<PageSnapshot>,
+}
+
+// Missing fields are empty so snapshots of older versions can still be read
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct PageSnapshot {
+    pub url: String,
+    pub status: Option<u32>,
+    pub error: Option<String>,
+    pub texts: Vec<String>,
+    pub comments: Vec<String>,
+    pub links: Vec<String>,
+    pub images: Vec<String>,
+    pub inputs: Vec<String>,
+    pub extracted: BTreeMap<String, Vec<String>>,
+}
+
+impl PageSnapshot {
+    fn from_node(node: &Node) -> Self {
+        let urls = |urls: &Option<Vec<url::Url>>| {
+            urls.iter().flatten().map(|url| url.to_string()).collect()
+        };
+        PageSnapshot {
+            url: node.url.to_string(),
+            status: node.status,
+            error: node.error.clone(),
+            texts: node.texts.clone().unwrap_or_default(),
+            comments: node.comments.clone().unwrap_or_default(),
+            links: urls(&node.links),
+            images: urls(&node.images),
+            inputs: node.inputs.clone().unwrap_or_default(),
+            extracted: node.extracted.clone(),
+        }
+    }
+}
+
+impl Snapshot {
+    pub fn from_root(root: &Arc<Mutex<Node>>, seed: &str) -> Result<Self, Box<dyn error::Error>> {
+        let mut pages: Vec<PageSnapshot> = Vec::new();
+        Node::explore(root, &mut |node: &mut Node| {
+            pages.push(PageSnapshot::from_node(node));
+            Ok(())
+        })?;
+        Ok(Snapshot {
+            seed: seed.to_owned(),
+            date: chrono::Utc::now().to_rfc3339(),
+            pages,
+        })
+    }
+
+    pub fn read(path: &Path) -> Result<Self, SnapshotError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| SnapshotError::Message(format!("can't read {}: {}", path.display(), e)))?;
+        serde_json::from_str(&content).map_err(|e| {
+            SnapshotError::Message(format!("{} is not a snapshot: {}", path.display(), e))
+        })
+    }
+}
+
+pub fn render(root: &Arc<Mutex<Node>>, seed: &str) -> Result<String, Box<dyn error::Error>> {
+    Ok(serde_json::to_string_pretty(&Snapshot::from_root(
+        root, seed,
+    )?)?)
+}
+
+pub enum SnapshotError {
+    IO(std::io::Error),
+    Message(String),
+}
+
+impl SnapshotError {
+    fn print(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::IO(e) => write!(f, "{}: {}", "Snapshot error".red(), e),
+            SnapshotError::Message(s) => write!(f, "{}: {}", "Snapshot error".red(), s),
+        }
+    }
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl fmt::Debug for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl error::Error for SnapshotError {}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(value: std::io::Error) -> Self {
+        SnapshotError::IO(value)
+    }
+}
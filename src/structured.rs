@@ -0,0 +1,263 @@
+use markup5ever::local_name;
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+use crate::extract::element_text;
+use crate::node::Node;
+
+// Schema.org like data embedded in the page
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Structured {
+    pub json_ld: Vec<Value>,
+    pub microdata: Vec<Value>,
+    pub rdfa: Vec<Value>,
+    // Problems found in the JSON-LD blocks
+    pub errors: Vec<String>,
+}
+
+impl Structured {
+    fn from_page(url: &Url, page: &Html) -> Self {
+        let mut structured = Structured::default();
+        structured.json_ld(page);
+        let items = Selector::parse("[itemscope]:not([itemprop])").unwrap();
+        structured.microdata = page
+            .select(&items)
+            .map(|item| microdata_item(item, url))
+            .collect();
+        let items = Selector::parse("[typeof]:not([property])").unwrap();
+        structured.rdfa = page
+            .select(&items)
+            .map(|item| rdfa_item(item, url))
+            .collect();
+        structured
+    }
+
+    fn json_ld(&mut self, page: &Html) {
+        let scripts = Selector::parse("script[type]").unwrap();
+        let blocks = page.select(&scripts).filter(|script| {
+            script
+                .value()
+                .attr("type")
+                .is_some_and(|kind| kind.trim().eq_ignore_ascii_case("application/ld+json"))
+        });
+        for (i, script) in blocks.enumerate() {
+            let block = i + 1;
+            let content = script.text().collect::<String>();
+            let value: Value = match serde_json::from_str(content.trim()) {
+                Ok(value) => value,
+                Err(e) => {
+                    self.errors.push(format!("JSON-LD block {block}: {e}"));
+                    continue;
+                }
+            };
+            // A block can hold a list of items
+            let items = match value {
+                Value::Array(items) => items,
+                value => vec![value],
+            };
+            for item in items {
+                match &item {
+                    Value::Object(object) => {
+                        if !object.contains_key("@context") {
+                            self.errors
+                                .push(format!("JSON-LD block {block}: missing @context"));
+                        }
+                        if !object.contains_key("@type") && !object.contains_key("@graph") {
+                            self.errors
+                                .push(format!("JSON-LD block {block}: missing @type"));
+                        }
+                        self.json_ld.push(item);
+                    }
+                    _ => self
+                        .errors
+                        .push(format!("JSON-LD block {block}: expected an object")),
+                }
+            }
+        }
+    }
+
+    // Every item on its own line, as json
+    pub fn lines(&self) -> Vec<String> {
+        let items = [
+            ("json-ld", &self.json_ld),
+            ("microdata", &self.microdata),
+            ("rdfa", &self.rdfa),
+        ];
+        items
+            .into_iter()
+            .flat_map(|(kind, items)| items.iter().map(move |item| format!("{kind}: {item}")))
+            .chain(self.errors.iter().map(|error| format!("error: {error}")))
+            .collect()
+    }
+}
+
+fn absolute(url: &Url, value: Option<&str>) -> Value {
+    value
+        .and_then(|value| Url::join(url, value.trim()).ok())
+        .map_or(Value::Null, |url| url.to_string().into())
+}
+
+// A property given several times becomes a list
+fn add(properties: &mut Map<String, Value>, name: &str, value: Value) {
+    match properties.get_mut(name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(first) => *first = Value::Array(vec![first.take(), value]),
+        None => {
+            properties.insert(name.to_owned(), value);
+        }
+    }
+}
+
+// Types and ids are lists separated by spaces
+fn tokens(value: &str) -> Value {
+    let tokens: Vec<&str> = value.split_whitespace().collect();
+    match tokens.as_slice() {
+        [token] => (*token).into(),
+        tokens => tokens.to_vec().into(),
+    }
+}
+
+fn microdata_item(element: ElementRef, url: &Url) -> Value {
+    let mut item = Map::new();
+    if let Some(kind) = element.value().attr("itemtype") {
+        item.insert("@type".to_owned(), tokens(kind));
+    }
+    if let Some(id) = element.value().attr("itemid") {
+        item.insert("@id".to_owned(), absolute(url, Some(id)));
+    }
+    microdata_properties(element, url, &mut item);
+    Value::Object(item)
+}
+
+// Properties of the item, without going into the nested items
+fn microdata_properties(element: ElementRef, url: &Url, item: &mut Map<String, Value>) {
+    for child in element.children().filter_map(ElementRef::wrap) {
+        let scope = child.value().attr("itemscope").is_some();
+        if let Some(names) = child.value().attr("itemprop") {
+            let value = if scope {
+                microdata_item(child, url)
+            } else {
+                microdata_value(child, url)
+            };
+            for name in names.split_whitespace() {
+                add(item, name, value.clone());
+            }
+        }
+        if !scope {
+            microdata_properties(child, url, item);
+        }
+    }
+}
+
+// Value of a property depends on the element holding it
+fn microdata_value(element: ElementRef, url: &Url) -> Value {
+    let value = element.value();
+    match value.name.local {
+        local_name!("meta") => value.attr("content").unwrap_or_default().into(),
+        local_name!("audio")
+        | local_name!("embed")
+        | local_name!("iframe")
+        | local_name!("img")
+        | local_name!("source")
+        | local_name!("track")
+        | local_name!("video") => absolute(url, value.attr("src")),
+        local_name!("a") | local_name!("area") | local_name!("link") => {
+            absolute(url, value.attr("href"))
+        }
+        local_name!("object") => absolute(url, value.attr("data")),
+        local_name!("data") | local_name!("meter") => {
+            value.attr("value").unwrap_or_default().into()
+        }
+        local_name!("time") => value
+            .attr("datetime")
+            .map_or_else(|| element_text(element), str::to_owned)
+            .into(),
+        _ => value
+            .attr("content")
+            .map_or_else(|| element_text(element), str::to_owned)
+            .into(),
+    }
+}
+
+fn rdfa_item(element: ElementRef, url: &Url) -> Value {
+    let mut item = Map::new();
+    // The vocabulary is given by the element or one of its ancestors
+    let vocab = std::iter::once(element)
+        .chain(element.ancestors().filter_map(ElementRef::wrap))
+        .find_map(|element| element.value().attr("vocab"));
+    if let Some(vocab) = vocab {
+        item.insert("@context".to_owned(), vocab.into());
+    }
+    if let Some(kind) = element
+        .value()
+        .attr("typeof")
+        .filter(|kind| !kind.trim().is_empty())
+    {
+        item.insert("@type".to_owned(), tokens(kind));
+    }
+    if let Some(id) = element.value().attr("resource") {
+        item.insert("@id".to_owned(), absolute(url, Some(id)));
+    }
+    rdfa_properties(element, url, &mut item);
+    Value::Object(item)
+}
+
+fn rdfa_properties(element: ElementRef, url: &Url, item: &mut Map<String, Value>) {
+    for child in element.children().filter_map(ElementRef::wrap) {
+        let nested = child.value().attr("typeof").is_some();
+        if let Some(names) = child.value().attr("property") {
+            let value = if nested {
+                // The vocabulary is only repeated when it changes
+                let mut nested = rdfa_item(child, url);
+                if let Value::Object(nested) = &mut nested {
+                    if nested.get("@context") == item.get("@context") {
+                        nested.remove("@context");
+                    }
+                }
+                nested
+            } else {
+                rdfa_value(child, url)
+            };
+            for name in names.split_whitespace() {
+                add(item, name, value.clone());
+            }
+        }
+        if !nested {
+            rdfa_properties(child, url, item);
+        }
+    }
+}
+
+fn rdfa_value(element: ElementRef, url: &Url) -> Value {
+    let value = element.value();
+    if let Some(content) = value.attr("content") {
+        return content.into();
+    }
+    if let Some(resource) = value.attr("resource") {
+        return absolute(url, Some(resource));
+    }
+    match value.name.local {
+        local_name!("a") | local_name!("area") | local_name!("link") => {
+            absolute(url, value.attr("href"))
+        }
+        local_name!("img")
+        | local_name!("audio")
+        | local_name!("video")
+        | local_name!("source")
+        | local_name!("iframe")
+        | local_name!("embed") => absolute(url, value.attr("src")),
+        local_name!("time") => value
+            .attr("datetime")
+            .map_or_else(|| element_text(element), str::to_owned)
+            .into(),
+        _ => element_text(element).into(),
+    }
+}
+
+pub fn extract_structured(node: &Arc<Mutex<Node>>, page: &Html) {
+    let mut node = node.lock().unwrap();
+    node.structured = Some(Structured::from_page(&node.url, page));
+}
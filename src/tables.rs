@@ -0,0 +1,263 @@
+use colored::Colorize;
+use markup5ever::local_name;
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+use std::{
+    fmt, fs,
+    sync::{Arc, Mutex},
+};
+
+use crate::extract::element_text;
+use crate::node::Node;
+
+// Limits given by the html specification
+const MAX_COLSPAN: usize = 1000;
+const MAX_ROWSPAN: usize = 65534;
+
+// Table of the page with its cells spread over the spanned columns and rows
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Table {
+    pub caption: Option<String>,
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    // The first cell of every row is a <th> naming the row
+    pub row_headers: bool,
+}
+
+struct Row {
+    cells: Vec<String>,
+    // Every cell is a <th>
+    header: bool,
+    first_th: bool,
+}
+
+fn span(cell: ElementRef, attr: &str, max: usize) -> usize {
+    cell.value()
+        .attr(attr)
+        .and_then(|span| span.trim().parse::<usize>().ok())
+        .map_or(1, |span| span.clamp(1, max))
+}
+
+// Rows of the table and whether they are in its head, without the nested tables
+fn rows(table: ElementRef) -> Vec<(ElementRef, bool)> {
+    let mut rows = Vec::new();
+    for child in table.children().filter_map(ElementRef::wrap) {
+        match child.value().name.local {
+            local_name!("tr") => rows.push((child, false)),
+            local_name!("thead") | local_name!("tbody") | local_name!("tfoot") => {
+                let head = child.value().name.local == local_name!("thead");
+                rows.extend(
+                    child
+                        .children()
+                        .filter_map(ElementRef::wrap)
+                        .filter(|row| row.value().name.local == local_name!("tr"))
+                        .map(|row| (row, head)),
+                );
+            }
+            _ => {}
+        }
+    }
+    rows
+}
+
+// Cells of the previous rows spanning over the next columns of the row
+fn fill(pending: &mut [Option<(String, usize)>], cells: &mut Vec<String>, kinds: &mut Vec<bool>) {
+    while let Some(Some((value, left))) = pending.get_mut(cells.len()) {
+        cells.push(value.clone());
+        kinds.push(false);
+        *left -= 1;
+        if *left == 0 {
+            pending[cells.len() - 1] = None;
+        }
+    }
+}
+
+impl Table {
+    pub fn from_element(table: ElementRef) -> Self {
+        // Cells spanning over the next rows, with the number of rows left, by column
+        let mut pending: Vec<Option<(String, usize)>> = Vec::new();
+        let mut grid: Vec<(Row, bool)> = Vec::new();
+        let mut section = None;
+        for (row, head) in rows(table) {
+            // Cells don't span over the next section, a rowspan of 0 ends with its section
+            if section != row.parent().map(|parent| parent.id()) {
+                section = row.parent().map(|parent| parent.id());
+                pending.clear();
+            }
+            let mut cells: Vec<String> = Vec::new();
+            let mut kinds: Vec<bool> = Vec::new();
+            let row_cells = row.children().filter_map(ElementRef::wrap).filter(|cell| {
+                matches!(
+                    cell.value().name.local,
+                    local_name!("td") | local_name!("th")
+                )
+            });
+            for cell in row_cells {
+                fill(&mut pending, &mut cells, &mut kinds);
+                let value = element_text(cell);
+                let th = cell.value().name.local == local_name!("th");
+                let rowspan = match cell.value().attr("rowspan").map(str::trim) {
+                    // Spans over the rest of the section
+                    Some("0") => MAX_ROWSPAN,
+                    _ => span(cell, "rowspan", MAX_ROWSPAN),
+                };
+                for _ in 0..span(cell, "colspan", MAX_COLSPAN) {
+                    if rowspan > 1 {
+                        if pending.len() <= cells.len() {
+                            pending.resize(cells.len() + 1, None);
+                        }
+                        pending[cells.len()] = Some((value.clone(), rowspan - 1));
+                    }
+                    cells.push(value.clone());
+                    kinds.push(th);
+                }
+            }
+            fill(&mut pending, &mut cells, &mut kinds);
+            if cells.is_empty() {
+                continue;
+            }
+            let row = Row {
+                header: kinds.iter().all(|th| *th),
+                first_th: kinds.first() == Some(&true),
+                cells,
+            };
+            grid.push((row, head));
+        }
+
+        // The header is the head of the table, or its first row when it only has <th>
+        let head = grid.iter().take_while(|(_, head)| *head).count();
+        let head = match head {
+            0 if grid.len() > 1 && grid[0].0.header => 1,
+            head => head,
+        };
+        let width = grid
+            .iter()
+            .map(|(row, _)| row.cells.len())
+            .max()
+            .unwrap_or(0);
+        let mut headers = vec![String::new(); if head > 0 { width } else { 0 }];
+        for (row, _) in &grid[..head] {
+            for (header, cell) in headers.iter_mut().zip(&row.cells) {
+                // Header rows split in several levels are joined
+                if !cell.is_empty() && !header.ends_with(cell.as_str()) {
+                    if !header.is_empty() {
+                        header.push_str(" / ");
+                    }
+                    header.push_str(cell);
+                }
+            }
+        }
+        let body = &grid[head..];
+        Table {
+            caption: table
+                .children()
+                .filter_map(ElementRef::wrap)
+                .find(|child| child.value().name.local == local_name!("caption"))
+                .map(element_text),
+            headers,
+            row_headers: !body.is_empty() && body.iter().all(|(row, _)| row.first_th),
+            rows: body
+                .iter()
+                .map(|(row, _)| {
+                    let mut cells = row.cells.clone();
+                    cells.resize(width, String::new());
+                    cells
+                })
+                .collect(),
+        }
+    }
+
+    // Headers and rows as csv
+    pub fn csv(&self) -> String {
+        let field = |value: &String| {
+            if value.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.clone()
+            }
+        };
+        let mut lines: Vec<String> = Vec::new();
+        if !self.headers.is_empty() {
+            lines.push(self.headers.iter().map(field).collect::<Vec<_>>().join(","));
+        }
+        for row in &self.rows {
+            lines.push(row.iter().map(field).collect::<Vec<_>>().join(","));
+        }
+        lines.join("\r\n") + "\r\n"
+    }
+
+    // Cells separated by tabulations, after the caption
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.caption.iter().cloned().collect();
+        if !self.headers.is_empty() {
+            lines.push(self.headers.join("\t"));
+        }
+        lines.extend(self.rows.iter().map(|row| row.join("\t")));
+        lines
+    }
+}
+
+pub fn extract_tables(node: &Arc<Mutex<Node>>, page: &Html) {
+    let tables = Selector::parse("table").unwrap();
+    node.lock().unwrap().tables = Some(
+        page.select(&tables)
+            .map(Table::from_element)
+            .filter(|table| !table.headers.is_empty() || !table.rows.is_empty())
+            .collect(),
+    );
+}
+
+// Write every table in its own csv file, numbered by page and by table
+pub fn write(root: &Arc<Mutex<Node>>, name: &str) -> Result<(), TablesError> {
+    let mut pages: Vec<(String, Vec<Table>)> = Vec::new();
+    Node::explore(root, &mut |node: &mut Node| {
+        if let Some(tables) = node.tables.take().filter(|tables| !tables.is_empty()) {
+            pages.push((node.url.to_string(), tables));
+        }
+        Ok(())
+    })
+    .map_err(|e| TablesError::Message(e.to_string()))?;
+
+    for (page, (url, tables)) in pages.into_iter().enumerate() {
+        for (index, table) in tables.iter().enumerate() {
+            let file_name = format!("{}-{}-{}.csv", name, page + 1, index + 1);
+            println!("Writing {} for {}", file_name, url.green());
+            fs::write(&file_name, table.csv())?;
+        }
+    }
+    Ok(())
+}
+
+pub enum TablesError {
+    IO(std::io::Error),
+    Message(String),
+}
+
+impl TablesError {
+    fn print(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TablesError::IO(e) => write!(f, "{}: {}", "Tables error".red(), e),
+            TablesError::Message(s) => write!(f, "{}: {}", "Tables error".red(), s),
+        }
+    }
+}
+
+impl fmt::Display for TablesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl fmt::Debug for TablesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl std::error::Error for TablesError {}
+
+impl From<std::io::Error> for TablesError {
+    fn from(value: std::io::Error) -> Self {
+        TablesError::IO(value)
+    }
+}
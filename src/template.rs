@@ -0,0 +1,170 @@
+use colored::Colorize;
+use minijinja::{context, Environment};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::cli::{Cli, TemplateScope};
+use crate::download::ImageFile;
+use crate::forms::Form;
+use crate::markdown::Markdown;
+use crate::meta::Meta;
+use crate::node::Node;
+use crate::patterns::Match;
+use crate::rules::Record;
+use crate::structured::Structured;
+use crate::tables::Table;
+
+// Fields of a node given to the template
+#[derive(Serialize)]
+pub struct Page {
+    id: String,
+    url: String,
+    error: Option<String>,
+    status: Option<u32>,
+    texts: Option<Vec<String>>,
+    visible: Option<Vec<String>>,
+    comments: Option<Vec<String>>,
+    links: Option<Vec<String>>,
+    images: Option<Vec<String>>,
+    image_files: Option<Vec<ImageFile>>,
+    inputs: Option<Vec<String>>,
+    forms: Option<Vec<Form>>,
+    meta: Option<Meta>,
+    structured: Option<Structured>,
+    tables: Option<Vec<Table>>,
+    markdown: Option<Markdown>,
+    extracted: BTreeMap<String, Vec<String>>,
+    matches: Vec<Match>,
+    records: Vec<Record>,
+    children: Vec<String>,
+}
+
+impl Page {
+    pub fn from_node(node: &Node) -> Self {
+        let urls = |urls: &Option<Vec<url::Url>>| {
+            urls.as_ref()
+                .map(|urls| urls.iter().map(|url| url.to_string()).collect())
+        };
+        Page {
+            id: node.id.clone(),
+            url: node.url.to_string(),
+            error: node.error.clone(),
+            status: node.status,
+            texts: node.texts.clone(),
+            visible: node.visible.clone(),
+            comments: node.comments.clone(),
+            links: urls(&node.links),
+            images: urls(&node.images),
+            image_files: node.image_files.clone(),
+            inputs: node.inputs.clone(),
+            forms: node.forms.clone(),
+            meta: node.meta.clone(),
+            structured: node.structured.clone(),
+            tables: node.tables.clone(),
+            markdown: node.markdown.clone(),
+            extracted: node.extracted.clone(),
+            matches: node.matches.clone(),
+            records: node.records.clone(),
+            children: node
+                .children
+                .iter()
+                .filter(|child| child.lock().unwrap().explored)
+                .map(|child| child.lock().unwrap().url.to_string())
+                .collect(),
+        }
+    }
+}
+
+// Render the template given by the user. In the page scope the template is rendered
+// for every page with the variable `page`, in the crawl scope it is rendered once
+// with the variable `pages`. `seed` is the url of the crawl in both cases, and the
+// renders of the pages are joined by a new line
+pub fn render(root: &Arc<Mutex<Node>>, args: &Cli) -> Result<String, TemplateError> {
+    let path = args
+        .template
+        .as_deref()
+        .ok_or(TemplateError::Message("no template given".to_owned()))?;
+    let source = fs::read_to_string(path)?;
+    // The name of the file decides if the output is escaped, like for `.html`
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let mut env = Environment::new();
+    env.add_template(&name, &source)?;
+    let template = env.get_template(&name)?;
+
+    let mut pages: Vec<Page> = Vec::new();
+    Node::explore(root, &mut |node: &mut Node| {
+        pages.push(Page::from_node(node));
+        Ok(())
+    })
+    .map_err(|e| TemplateError::Message(e.to_string()))?;
+
+    match args.template_scope {
+        TemplateScope::Crawl => Ok(template.render(context! { seed => args.url(), pages })?),
+        TemplateScope::Page => Ok(pages
+            .iter()
+            .map(|page| template.render(context! { seed => args.url(), page }))
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n")),
+    }
+}
+
+// Extension of the file generated by the template, `page.html.j2` gives `html`
+pub fn extension(path: &Path) -> String {
+    let path = match path.extension().and_then(|e| e.to_str()) {
+        Some("j2" | "jinja" | "jinja2" | "tmpl") => Path::new(path.file_stem().unwrap_or_default()),
+        _ => path,
+    };
+    path.extension()
+        .map_or("txt".to_owned(), |e| e.to_string_lossy().into_owned())
+}
+
+pub enum TemplateError {
+    IO(std::io::Error),
+    Render(minijinja::Error),
+    Message(String),
+}
+
+impl TemplateError {
+    fn print(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::IO(e) => write!(f, "{}: {}", "Template error".red(), e),
+            TemplateError::Render(e) => write!(f, "{}: {:#}", "Template error".red(), e),
+            TemplateError::Message(s) => write!(f, "{}: {}", "Template error".red(), s),
+        }
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl fmt::Debug for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl From<std::io::Error> for TemplateError {
+    fn from(value: std::io::Error) -> Self {
+        TemplateError::IO(value)
+    }
+}
+
+impl From<minijinja::Error> for TemplateError {
+    fn from(value: minijinja::Error) -> Self {
+        TemplateError::Render(value)
+    }
+}
@@ -0,0 +1,335 @@
+use markup5ever::local_name;
+use regex::Regex;
+use scraper::{node::Element, ElementRef, Html, Node as HtmlNode, Selector};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use crate::extract::element_text;
+use crate::node::Node;
+
+// Classes and ids of the parts around the content, and of the content itself
+static UNLIKELY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)banner|breadcrumb|comment|cookie|footer|footnote|header|menu|modal|nav|popup|promo|related|share|sidebar|social|sponsor|subscribe|widget|(^|[-_ ])ads?([-_ ]|$)",
+    )
+    .unwrap()
+});
+static LIKELY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)article|body|content|entry|main|page|post|story|text").unwrap()
+});
+
+// Elements whose content is never shown as text
+fn invisible(element: &Element) -> bool {
+    matches!(
+        element.name.local,
+        local_name!("script")
+            | local_name!("style")
+            | local_name!("noscript")
+            | local_name!("template")
+            | local_name!("head")
+            | local_name!("svg")
+            | local_name!("canvas")
+            | local_name!("iframe")
+            | local_name!("object")
+            | local_name!("embed")
+            | local_name!("audio")
+            | local_name!("video")
+            | local_name!("select")
+    )
+}
+
+fn hidden(element: &Element) -> bool {
+    let style = element
+        .attr("style")
+        .unwrap_or_default()
+        .to_lowercase()
+        .replace(char::is_whitespace, "");
+    element.attr("hidden").is_some()
+        || element.attr("aria-hidden") == Some("true")
+        || style.contains("display:none")
+        || style.contains("visibility:hidden")
+}
+
+fn class_and_id(element: &Element) -> String {
+    format!(
+        "{} {}",
+        element.attr("class").unwrap_or_default(),
+        element.attr("id").unwrap_or_default()
+    )
+}
+
+// Navigation, header, footer and sidebars around the main content
+fn boilerplate(element: ElementRef) -> bool {
+    let value = element.value();
+    match value.name.local {
+        local_name!("nav") | local_name!("aside") | local_name!("footer") | local_name!("form") => {
+            true
+        }
+        // The header of an article holds its title
+        local_name!("header") => {
+            let headings = Selector::parse("h1, h2, h3, h4, h5, h6").unwrap();
+            element.select(&headings).next().is_none()
+        }
+        local_name!("html")
+        | local_name!("body")
+        | local_name!("main")
+        | local_name!("article") => false,
+        _ => {
+            let names = class_and_id(value);
+            UNLIKELY.is_match(&names) && !LIKELY.is_match(&names)
+        }
+    }
+}
+
+// Element left out of the text, the boilerplate only when looking for the main content
+pub fn skipped(element: ElementRef, main: bool) -> bool {
+    invisible(element.value()) || hidden(element.value()) || (main && boilerplate(element))
+}
+
+// Part of the text of the element found in its links
+fn link_density(element: ElementRef, length: usize) -> f64 {
+    let links = Selector::parse("a").unwrap();
+    let linked: usize = element
+        .select(&links)
+        .map(|link| element_text(link).len())
+        .sum();
+    linked as f64 / length.max(1) as f64
+}
+
+// Score given to an element by its tag and its names
+fn weight(element: ElementRef) -> f64 {
+    let value = element.value();
+    let tag = match value.name.local {
+        local_name!("div") | local_name!("article") | local_name!("main") => 5.0,
+        local_name!("pre") | local_name!("td") | local_name!("blockquote") => 3.0,
+        local_name!("ol") | local_name!("ul") | local_name!("dl") | local_name!("li") => -3.0,
+        local_name!("form") | local_name!("th") => -5.0,
+        _ => 0.0,
+    };
+    let names = class_and_id(value);
+    let names = match (LIKELY.is_match(&names), UNLIKELY.is_match(&names)) {
+        (true, false) => 25.0,
+        (false, true) => -25.0,
+        _ => 0.0,
+    };
+    tag + names
+}
+
+// Element holding the content of the page, found like the readability tools do: the
+// main element or the only article, else the element whose paragraphs score the best
+pub fn main_content(page: &Html) -> ElementRef<'_> {
+    let body = page
+        .select(&Selector::parse("body").unwrap())
+        .next()
+        .unwrap_or(page.root_element());
+    let main = Selector::parse("main, [role=main]").unwrap();
+    if let Some(main) = page.select(&main).find(|main| !skipped(*main, false)) {
+        return main;
+    }
+    let articles: Vec<ElementRef> = page.select(&Selector::parse("article").unwrap()).collect();
+    if let [article] = articles.as_slice() {
+        return *article;
+    }
+
+    // Paragraphs give their score to their parent and half of it to their grandparent
+    let mut scores: HashMap<_, f64> = HashMap::new();
+    let paragraphs = Selector::parse("p, pre, td, blockquote").unwrap();
+    for paragraph in page.select(&paragraphs) {
+        let content = element_text(paragraph);
+        if content.len() < 25 {
+            continue;
+        }
+        let score = 1.0 + content.matches(',').count() as f64 + (content.len() / 100).min(3) as f64;
+        let ancestors = paragraph.ancestors().filter_map(ElementRef::wrap).take(2);
+        for (level, ancestor) in ancestors.enumerate() {
+            *scores
+                .entry(ancestor.id())
+                .or_insert_with(|| weight(ancestor)) += score / (level + 1) as f64;
+        }
+    }
+    // Every candidate is scored once, in the order of the document so the first
+    // of the best candidates is kept
+    let candidates: Vec<(ElementRef, f64)> = page
+        .root_element()
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter_map(|element| {
+            let score = scores.get(&element.id())?;
+            let density = link_density(element, element_text(element).len());
+            Some((element, score * (1.0 - density)))
+        })
+        .collect();
+    let Some(mut best) = candidates
+        .iter()
+        .filter(|candidate| !skipped(candidate.0, true))
+        .copied()
+        .reduce(|best, candidate| {
+            if candidate.1 > best.1 {
+                candidate
+            } else {
+                best
+            }
+        })
+    else {
+        return body;
+    };
+    // The content can be split between several children of a better parent
+    let scored: HashMap<_, (ElementRef, f64)> = candidates
+        .iter()
+        .map(|candidate| (candidate.0.id(), *candidate))
+        .collect();
+    while let Some(parent) = best
+        .0
+        .parent()
+        .and_then(|parent| scored.get(&parent.id()))
+        .filter(|parent| parent.1 > best.1)
+    {
+        best = *parent;
+    }
+    best.0
+}
+
+// Text split in blocks, with the list items numbered or bulleted
+#[derive(Default)]
+struct Blocks {
+    blocks: Vec<String>,
+    current: String,
+    prefix: Option<String>,
+    // Number of the next item of every open list, none for the bulleted ones
+    lists: Vec<Option<usize>>,
+}
+
+impl Blocks {
+    // The inline text gathered so far becomes a block, on its lines
+    fn flush(&mut self) {
+        let lines: Vec<String> = self
+            .current
+            .split('\n')
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .collect();
+        self.current.clear();
+        if !lines.is_empty() {
+            let prefix = self.prefix.take().unwrap_or_default();
+            self.blocks.push(prefix + &lines.join("\n"));
+        }
+    }
+
+    fn walk(&mut self, element: ElementRef, main: bool) {
+        for child in element.children() {
+            match child.value() {
+                // Only the <br> break the lines
+                HtmlNode::Text(text) => self.current.push_str(&text.replace('\n', " ")),
+                HtmlNode::Element(_) => {
+                    let child = ElementRef::wrap(child).unwrap();
+                    if !skipped(child, main) {
+                        self.element(child, main);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn element(&mut self, element: ElementRef, main: bool) {
+        match element.value().name.local {
+            local_name!("br") => self.current.push('\n'),
+            local_name!("td") | local_name!("th") => {
+                self.current.push(' ');
+                self.walk(element, main);
+                self.current.push(' ');
+            }
+            // Preformatted text keeps its spaces
+            local_name!("pre") => {
+                self.flush();
+                let content = element.text().collect::<String>();
+                let content = content.trim_matches('\n');
+                if !content.trim().is_empty() {
+                    self.blocks.push(content.to_owned());
+                }
+            }
+            local_name!("ul") | local_name!("ol") => {
+                self.flush();
+                let ordered = element.value().name.local == local_name!("ol");
+                let start = element
+                    .value()
+                    .attr("start")
+                    .and_then(|start| start.trim().parse().ok())
+                    .unwrap_or(1);
+                self.lists.push(ordered.then_some(start));
+                self.walk(element, main);
+                self.flush();
+                self.lists.pop();
+            }
+            local_name!("li") => {
+                self.flush();
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                self.prefix = Some(match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{indent}{}. ", *number - 1)
+                    }
+                    _ => format!("{indent}- "),
+                });
+                self.walk(element, main);
+                self.flush();
+                self.prefix = None;
+            }
+            local_name!("address")
+            | local_name!("article")
+            | local_name!("aside")
+            | local_name!("blockquote")
+            | local_name!("caption")
+            | local_name!("dd")
+            | local_name!("details")
+            | local_name!("dialog")
+            | local_name!("div")
+            | local_name!("dl")
+            | local_name!("dt")
+            | local_name!("fieldset")
+            | local_name!("figcaption")
+            | local_name!("figure")
+            | local_name!("footer")
+            | local_name!("form")
+            | local_name!("h1")
+            | local_name!("h2")
+            | local_name!("h3")
+            | local_name!("h4")
+            | local_name!("h5")
+            | local_name!("h6")
+            | local_name!("header")
+            | local_name!("hr")
+            | local_name!("legend")
+            | local_name!("main")
+            | local_name!("nav")
+            | local_name!("p")
+            | local_name!("section")
+            | local_name!("summary")
+            | local_name!("table")
+            | local_name!("tr") => {
+                self.flush();
+                self.walk(element, main);
+                self.flush();
+            }
+            _ => self.walk(element, main),
+        }
+    }
+}
+
+// Visible text of the page as paragraphs, headings and list items
+pub fn visible(root: ElementRef, main: bool) -> Vec<String> {
+    let mut blocks = Blocks::default();
+    blocks.walk(root, main);
+    blocks.flush();
+    blocks.blocks
+}
+
+pub fn extract_visible(node: &Arc<Mutex<Node>>, page: &Html, main: bool) {
+    let root = match main {
+        true => main_content(page),
+        false => page.root_element(),
+    };
+    node.lock().unwrap().visible = Some(visible(root, main));
+}
@@ -0,0 +1,275 @@
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use flate2::{write::GzEncoder, Compression};
+use sha1::{Digest, Sha1};
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Write},
+    sync::{Arc, Mutex},
+};
+use url::Url;
+
+use crate::cli::Cli;
+use crate::network::Exchange;
+use crate::node::Node;
+
+// Write every exchange recorded during the crawl in `<name>.warc.gz`.
+// Each record is a gzip member so the file can be read by any replay tool
+pub fn write(root: &Arc<Mutex<Node>>, args: &Cli, name: &str) -> Result<(), WarcError> {
+    let mut exchanges: Vec<Exchange> = Vec::new();
+    Node::explore(root, &mut |node: &mut Node| {
+        exchanges.extend(node.exchanges.take().unwrap_or_default());
+        Ok(())
+    })
+    .map_err(|e| WarcError::Message(e.to_string()))?;
+
+    let file_name = format!("{name}.warc.gz");
+    let mut file = File::create(&file_name)?;
+    let now = Utc::now();
+
+    let warcinfo_id = record_id();
+    let warcinfo = format!(
+        "software: coma/{}\r\nformat: WARC File Format 1.1\r\nconformsTo: http://iipc.github.io/warc-specifications/specifications/warc-format/warc-1.1/\r\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    write_record(
+        &mut file,
+        &[
+            ("WARC-Type", "warcinfo".to_owned()),
+            ("WARC-Record-ID", warcinfo_id.clone()),
+            ("WARC-Date", warc_date(&now)),
+            ("WARC-Filename", file_name.clone()),
+            ("Content-Type", "application/warc-fields".to_owned()),
+        ],
+        warcinfo.as_bytes(),
+    )?;
+
+    // Configuration of the crawl
+    let contents: Vec<String> = args
+        .content
+        .iter()
+        .map(|content| format!("{content:?}").to_lowercase())
+        .collect();
+    let metadata = format!(
+        "seed: {}\r\ndepth: {}\r\nbound: {}\r\nthread: {}\r\nexternal: {}\r\ncontent: {}\r\n",
+        args.url(),
+        args.depth,
+        args.bound,
+        args.thread,
+        args.external,
+        contents.join(",")
+    );
+    write_record(
+        &mut file,
+        &[
+            ("WARC-Type", "metadata".to_owned()),
+            ("WARC-Record-ID", record_id()),
+            ("WARC-Date", warc_date(&now)),
+            ("WARC-Target-URI", args.url().to_owned()),
+            ("WARC-Warcinfo-ID", warcinfo_id.clone()),
+            ("Content-Type", "application/warc-fields".to_owned()),
+        ],
+        metadata.as_bytes(),
+    )?;
+
+    for exchange in &exchanges {
+        let date = warc_date(&exchange.date);
+        let response_id = record_id();
+        let body = exchange.body.as_deref().unwrap_or_default();
+        let response = http_response(exchange, body);
+        let mut headers = vec![
+            ("WARC-Type", "response".to_owned()),
+            ("WARC-Record-ID", response_id.clone()),
+            ("WARC-Date", date.clone()),
+            ("WARC-Target-URI", exchange.url.clone()),
+            ("WARC-Warcinfo-ID", warcinfo_id.clone()),
+            ("WARC-Payload-Digest", digest(body)),
+            ("WARC-Block-Digest", digest(&response)),
+            (
+                "Content-Type",
+                "application/http;msgtype=response".to_owned(),
+            ),
+        ];
+        if let Some(ip) = &exchange.remote_ip {
+            headers.push(("WARC-IP-Address", ip.clone()));
+        }
+        write_record(&mut file, &headers, &response)?;
+
+        let request = http_request(exchange);
+        write_record(
+            &mut file,
+            &[
+                ("WARC-Type", "request".to_owned()),
+                ("WARC-Record-ID", record_id()),
+                ("WARC-Date", date),
+                ("WARC-Target-URI", exchange.url.clone()),
+                ("WARC-Warcinfo-ID", warcinfo_id.clone()),
+                ("WARC-Concurrent-To", response_id),
+                ("WARC-Block-Digest", digest(&request)),
+                (
+                    "Content-Type",
+                    "application/http;msgtype=request".to_owned(),
+                ),
+            ],
+            &request,
+        )?;
+    }
+
+    println!(
+        "Archived {} responses in {}",
+        exchanges.len().to_string().green(),
+        file_name
+    );
+    Ok(())
+}
+
+fn write_record(file: &mut File, headers: &[(&str, String)], block: &[u8]) -> io::Result<()> {
+    let mut record = GzEncoder::new(Vec::new(), Compression::default());
+    record.write_all(b"WARC/1.1\r\n")?;
+    for (name, value) in headers {
+        write!(record, "{name}: {value}\r\n")?;
+    }
+    write!(record, "Content-Length: {}\r\n\r\n", block.len())?;
+    record.write_all(block)?;
+    record.write_all(b"\r\n\r\n")?;
+    file.write_all(&record.finish()?)
+}
+
+fn http_request(exchange: &Exchange) -> Vec<u8> {
+    let url = Url::parse(&exchange.url).ok();
+    let target = url.as_ref().map_or_else(
+        || exchange.url.clone(),
+        |url| match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_owned(),
+        },
+    );
+    let mut request = format!("{} {} HTTP/1.1\r\n", exchange.method, target);
+
+    // HTTP/2 pseudo headers have no equivalent, the host must be given back
+    let headers: Vec<&(String, String)> = exchange
+        .request_headers
+        .iter()
+        .filter(|(name, _)| !name.starts_with(':'))
+        .collect();
+    if !headers
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case("host"))
+    {
+        if let Some(host) = url.as_ref().and_then(|url| url.host_str()) {
+            request.push_str(&format!("Host: {host}\r\n"));
+        }
+    }
+    for (name, value) in headers {
+        request.push_str(&format!("{name}: {value}\r\n"));
+    }
+    request.push_str("\r\n");
+
+    let mut request = request.into_bytes();
+    if let Some(data) = &exchange.post_data {
+        request.extend_from_slice(data.as_bytes());
+    }
+    request
+}
+
+fn http_response(exchange: &Exchange, body: &[u8]) -> Vec<u8> {
+    let version = if exchange.protocol.eq_ignore_ascii_case("http/1.0") {
+        "HTTP/1.0"
+    } else {
+        "HTTP/1.1"
+    };
+    let reason = if exchange.status_text.is_empty() {
+        reqwest::StatusCode::from_u16(exchange.status as u16)
+            .ok()
+            .and_then(|status| status.canonical_reason())
+            .unwrap_or_default()
+    } else {
+        &exchange.status_text
+    };
+    let mut response = format!("{} {} {}\r\n", version, exchange.status, reason);
+
+    // The browser gives the decoded body, the headers must describe it
+    for (name, value) in &exchange.response_headers {
+        if ["content-encoding", "transfer-encoding", "content-length"]
+            .iter()
+            .any(|skip| name.eq_ignore_ascii_case(skip))
+        {
+            continue;
+        }
+        // Repeated headers are joined with a newline
+        for value in value.split('\n') {
+            response.push_str(&format!("{name}: {value}\r\n"));
+        }
+    }
+    response.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+
+    let mut response = response.into_bytes();
+    response.extend_from_slice(body);
+    response
+}
+
+fn record_id() -> String {
+    format!("<urn:uuid:{}>", uuid::Uuid::new_v4())
+}
+
+fn warc_date(date: &DateTime<Utc>) -> String {
+    date.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+fn digest(data: &[u8]) -> String {
+    format!("sha1:{}", base32(&Sha1::digest(data)))
+}
+
+// RFC 4648 base32, used by WARC digests
+fn base32(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let mut output = String::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for byte in data {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        output.push(ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    output
+}
+
+pub enum WarcError {
+    IO(std::io::Error),
+    Message(String),
+}
+
+impl WarcError {
+    fn print(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarcError::IO(e) => write!(f, "{}: {}", "Warc error".red(), e),
+            WarcError::Message(s) => write!(f, "{}: {}", "Warc error".red(), s),
+        }
+    }
+}
+
+impl fmt::Display for WarcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl fmt::Debug for WarcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f)
+    }
+}
+
+impl std::error::Error for WarcError {}
+
+impl From<std::io::Error> for WarcError {
+    fn from(value: std::io::Error) -> Self {
+        WarcError::IO(value)
+    }
+}
@@ -0,0 +1,111 @@
+use colored::Colorize;
+use serde::Serialize;
+use std::{error, fs::OpenOptions, io::Write, path::Path};
+
+use crate::cli::Display;
+use crate::config::Config;
+use crate::diff::{self, CrawlDiff, PageDiff};
+use crate::snapshot::Snapshot;
+
+// Change found between two crawls, sent to the log and the webhook
+#[derive(Serialize)]
+struct Event<'a> {
+    // page_added, page_removed or page_changed
+    event: &'static str,
+    // Date of the crawl that found the change
+    date: &'a str,
+    url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    changes: Option<&'a PageDiff>,
+}
+
+fn events(diff: &CrawlDiff) -> Vec<Event<'_>> {
+    let event = |event, url, changes| Event {
+        event,
+        date: &diff.new,
+        url,
+        changes,
+    };
+    let added = diff.added.iter().map(|url| event("page_added", url, None));
+    let removed = diff
+        .removed
+        .iter()
+        .map(|url| event("page_removed", url, None));
+    let changed = diff
+        .changed
+        .iter()
+        .map(|page| event("page_changed", &page.url, Some(page)));
+    added.chain(removed).chain(changed).collect()
+}
+
+// Crawl until stopped, every crawl is compared to the previous one
+pub async fn run(conf: &mut Config) -> Result<(), Box<dyn error::Error>> {
+    let Display::Watch {
+        interval,
+        log,
+        webhook,
+        ..
+    } = conf.args.cmd.clone()
+    else {
+        return Ok(());
+    };
+    let client = reqwest::Client::new();
+    let mut previous: Option<Snapshot> = None;
+    loop {
+        // A failed crawl is skipped, the next one is compared to the last success
+        match crate::crawl(conf).await {
+            Err(e) => eprintln!("{} {}", "Crawl failed:".red(), e),
+            Ok(()) => {
+                let snapshot = Snapshot::from_root(&conf.root, conf.args.url())?;
+                match &previous {
+                    Some(previous) => {
+                        let diff = diff::compare(previous, &snapshot);
+                        print!("{diff}");
+                        let events = events(&diff);
+                        if let Some(log) = &log {
+                            append(log, &events)?;
+                        }
+                        if let Some(webhook) = &webhook {
+                            send(&client, webhook, &events).await;
+                        }
+                    }
+                    None => println!(
+                        "First crawl with {} pages, the next ones are compared to it",
+                        snapshot.pages.len()
+                    ),
+                }
+                previous = Some(snapshot);
+            }
+        }
+        conf.reset();
+        println!("Next crawl in {}", humantime::format_duration(interval));
+        tokio::time::sleep(interval).await;
+    }
+}
+
+fn append(path: &Path, events: &[Event<'_>]) -> Result<(), Box<dyn error::Error>> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for event in events {
+        writeln!(file, "{}", serde_json::to_string(event)?)?;
+    }
+    Ok(())
+}
+
+// The webhook being down doesn't stop the watch
+async fn send(client: &reqwest::Client, url: &str, events: &[Event<'_>]) {
+    for event in events {
+        let Ok(body) = serde_json::to_string(event) else {
+            continue;
+        };
+        let response = client
+            .post(url)
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = response {
+            eprintln!("{} {}", "Webhook failed:".red(), e);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+// Force directed graph drawn in SVG. It has no dependency so the page
+// works without network access.
+//
+// data: { nodes: [{ id, label, ... }], edges: [{ from, to, weight, tree, ... }] }
+// options.onSelect(node) is called with the clicked node, or null on the background
+// options.radius(node) and options.color(node) give the look of the nodes
+function drawGraph(container, data, options) {
+  const svgNS = "http://www.w3.org/2000/svg";
+  const radius = options.radius || (() => 8);
+  const color = options.color || (() => "#4682b4");
+  const onSelect = options.onSelect || (() => {});
+
+  function element(name, attributes, parent) {
+    const el = document.createElementNS(svgNS, name);
+    for (const [key, value] of Object.entries(attributes)) {
+      el.setAttribute(key, value);
+    }
+    if (parent) {
+      parent.appendChild(el);
+    }
+    return el;
+  }
+
+  const width = container.clientWidth || 800;
+  const height = container.clientHeight || 600;
+  const svg = element("svg", { width: "100%", height: "100%" }, container);
+  const defs = element("defs", {}, svg);
+  const marker = element("marker", {
+    id: "arrow",
+    viewBox: "0 0 10 10",
+    refX: "10",
+    refY: "5",
+    markerWidth: "6",
+    markerHeight: "6",
+    orient: "auto-start-reverse",
+  }, defs);
+  element("path", { d: "M 0 0 L 10 5 L 0 10 z", fill: "#888" }, marker);
+
+  const viewport = element("g", {}, svg);
+  const edgeLayer = element("g", {}, viewport);
+  const nodeLayer = element("g", {}, viewport);
+
+  // Nodes start on a circle so the simulation doesn't begin with overlaps
+  const nodes = data.nodes.map((node, i) => {
+    const angle = (2 * Math.PI * i) / Math.max(data.nodes.length, 1);
+    const spread = Math.min(width, height) / 3;
+    return {
+      data: node,
+      x: width / 2 + spread * Math.cos(angle),
+      y: height / 2 + spread * Math.sin(angle),
+      vx: 0,
+      vy: 0,
+      fixed: false,
+    };
+  });
+  const byId = new Map(nodes.map((node) => [node.data.id, node]));
+  const keys = new Set(data.edges.map((edge) => edge.from + " " + edge.to));
+  const edges = data.edges
+    .filter((edge) => byId.has(edge.from) && byId.has(edge.to))
+    .map((edge) => ({
+      data: edge,
+      source: byId.get(edge.from),
+      target: byId.get(edge.to),
+      // Edges in both directions are curved to not overlap
+      curved: keys.has(edge.to + " " + edge.from),
+    }));
+
+  for (const edge of edges) {
+    edge.el = element("path", {
+      fill: "none",
+      stroke: edge.data.tree === false ? "#add8e6" : "#4682b4",
+      "stroke-width": Math.min(1 + (edge.data.weight || 1), 8),
+      "stroke-dasharray": edge.data.tree === false ? "10 5" : "none",
+      "marker-end": "url(#arrow)",
+    }, edgeLayer);
+    const title = element("title", {}, edge.el);
+    title.textContent = options.edgeTitle ? options.edgeTitle(edge.data) : edge.data.from + " -> " + edge.data.to;
+  }
+
+  for (const node of nodes) {
+    node.el = element("g", { cursor: "pointer" }, nodeLayer);
+    node.circle = element("circle", {
+      r: radius(node.data),
+      fill: color(node.data),
+      stroke: "#fff",
+      "stroke-width": 1.5,
+    }, node.el);
+    node.label = element("text", {
+      x: radius(node.data) + 4,
+      y: 4,
+      "font-size": 13,
+      "font-family": "sans-serif",
+    }, node.el);
+    const text = node.data.label || node.data.id;
+    node.label.textContent = text.length > 50 ? text.slice(0, 47) + "..." : text;
+    const title = element("title", {}, node.el);
+    title.textContent = text;
+  }
+
+  function render() {
+    for (const edge of edges) {
+      const { source, target } = edge;
+      const dx = target.x - source.x;
+      const dy = target.y - source.y;
+      const distance = Math.hypot(dx, dy) || 1;
+      // Stop at the border of the circle so the arrow is visible
+      const end = radius(target.data) + 2;
+      const x2 = target.x - (dx / distance) * end;
+      const y2 = target.y - (dy / distance) * end;
+      if (edge.curved) {
+        const cx = (source.x + x2) / 2 - (dy / distance) * 25;
+        const cy = (source.y + y2) / 2 + (dx / distance) * 25;
+        edge.el.setAttribute("d", `M ${source.x} ${source.y} Q ${cx} ${cy} ${x2} ${y2}`);
+      } else {
+        edge.el.setAttribute("d", `M ${source.x} ${source.y} L ${x2} ${y2}`);
+      }
+    }
+    for (const node of nodes) {
+      node.el.setAttribute("transform", `translate(${node.x}, ${node.y})`);
+    }
+  }
+
+  // Repulsion between every node, springs on the edges and gravity to the center
+  let alpha = 1;
+  let running = false;
+  function tick() {
+    const length = 120;
+    for (let i = 0; i < nodes.length; i++) {
+      for (let j = i + 1; j < nodes.length; j++) {
+        const a = nodes[i];
+        const b = nodes[j];
+        let dx = b.x - a.x;
+        let dy = b.y - a.y;
+        let distance2 = dx * dx + dy * dy;
+        if (distance2 < 0.01) {
+          dx = Math.random() - 0.5;
+          dy = Math.random() - 0.5;
+          distance2 = 0.01;
+        }
+        const force = (length * length) / distance2;
+        a.vx -= dx * force * 0.05;
+        a.vy -= dy * force * 0.05;
+        b.vx += dx * force * 0.05;
+        b.vy += dy * force * 0.05;
+      }
+    }
+    for (const { source, target } of edges) {
+      const dx = target.x - source.x;
+      const dy = target.y - source.y;
+      const distance = Math.hypot(dx, dy) || 1;
+      const force = ((distance - length) / distance) * 0.05;
+      source.vx += dx * force;
+      source.vy += dy * force;
+      target.vx -= dx * force;
+      target.vy -= dy * force;
+    }
+    for (const node of nodes) {
+      node.vx += (width / 2 - node.x) * 0.005;
+      node.vy += (height / 2 - node.y) * 0.005;
+      if (!node.fixed) {
+        node.x += Math.max(-50, Math.min(50, node.vx * alpha));
+        node.y += Math.max(-50, Math.min(50, node.vy * alpha));
+      }
+      node.vx *= 0.6;
+      node.vy *= 0.6;
+    }
+  }
+
+  function animate() {
+    tick();
+    render();
+    alpha *= 0.98;
+    if (alpha > 0.01) {
+      requestAnimationFrame(animate);
+    } else {
+      running = false;
+    }
+  }
+
+  function restart(value) {
+    alpha = Math.max(alpha, value);
+    if (!running) {
+      running = true;
+      requestAnimationFrame(animate);
+    }
+  }
+
+  // Pan with the background, zoom with the wheel and move the nodes by dragging
+  const view = { x: 0, y: 0, scale: 1 };
+  function applyView() {
+    viewport.setAttribute("transform", `translate(${view.x}, ${view.y}) scale(${view.scale})`);
+  }
+  function toGraph(event) {
+    const box = svg.getBoundingClientRect();
+    return {
+      x: (event.clientX - box.left - view.x) / view.scale,
+      y: (event.clientY - box.top - view.y) / view.scale,
+    };
+  }
+
+  let dragged = null;
+  let panning = null;
+  let moved = false;
+  for (const node of nodes) {
+    node.el.addEventListener("mousedown", (event) => {
+      event.stopPropagation();
+      dragged = node;
+      moved = false;
+      node.fixed = true;
+    });
+    node.el.addEventListener("click", (event) => {
+      event.stopPropagation();
+      if (!moved) {
+        onSelect(node.data);
+      }
+    });
+  }
+  svg.addEventListener("mousedown", (event) => {
+    panning = { x: event.clientX - view.x, y: event.clientY - view.y };
+    moved = false;
+  });
+  window.addEventListener("mousemove", (event) => {
+    if (dragged) {
+      const point = toGraph(event);
+      dragged.x = point.x;
+      dragged.y = point.y;
+      moved = true;
+      restart(0.3);
+    } else if (panning) {
+      view.x = event.clientX - panning.x;
+      view.y = event.clientY - panning.y;
+      moved = true;
+      applyView();
+    }
+  });
+  window.addEventListener("mouseup", () => {
+    if (dragged) {
+      dragged.fixed = false;
+    }
+    dragged = null;
+    panning = null;
+  });
+  svg.addEventListener("click", () => {
+    if (!moved) {
+      onSelect(null);
+    }
+  });
+  svg.addEventListener("wheel", (event) => {
+    event.preventDefault();
+    const box = svg.getBoundingClientRect();
+    const factor = event.deltaY < 0 ? 1.1 : 1 / 1.1;
+    const x = event.clientX - box.left;
+    const y = event.clientY - box.top;
+    view.x = x - (x - view.x) * factor;
+    view.y = y - (y - view.y) * factor;
+    view.scale *= factor;
+    applyView();
+  }, { passive: false });
+
+  render();
+  restart(1);
+
+  return {
+    // Apply again the look of the nodes, after the options changed
+    restyle() {
+      for (const node of nodes) {
+        node.circle.setAttribute("r", radius(node.data));
+        node.circle.setAttribute("fill", color(node.data));
+        node.label.setAttribute("x", radius(node.data) + 4);
+      }
+      render();
+    },
+  };
+}